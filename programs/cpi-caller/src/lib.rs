@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program::invoke
+};
+
+declare_id!("AdayCxezRkZSCFCaZ55YPMokYpPZY5NFgoE2XeymHqga");
+
+// Test helper: lets bankrun tests run lending instructions below the
+// transaction level, e.g. to check that flash loans reject CPI callers
+#[program]
+pub mod cpi_caller {
+    use super::*;
+
+    /// Invokes `program` with `data` and the remaining accounts as its accounts
+    pub fn forward<'info>(ctx: Context<'_, '_, '_, 'info, Forward<'info>>, data: Vec<u8>) -> Result<()> {
+        let accounts = ctx
+            .remaining_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect();
+
+        let instruction = Instruction {
+            program_id: ctx.accounts.program.key(),
+            accounts,
+            data,
+        };
+
+        let mut account_infos = ctx.remaining_accounts.to_vec();
+        account_infos.push(ctx.accounts.program.to_account_info());
+        invoke(&instruction, &account_infos)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Forward<'info> {
+    /// CHECK: any program, invoked with the remaining accounts
+    pub program: UncheckedAccount<'info>,
+}
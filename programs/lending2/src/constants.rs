@@ -1,13 +1,76 @@
 use anchor_lang::prelude::*;
 #[constant]
-pub const MAX_AGE: u64 = 100;
+pub const DEFAULT_MAX_PRICE_AGE: u64 = 100;
+#[constant]
+pub const DEFAULT_MAX_CONFIDENCE_BPS: u64 = 200;
+#[constant]
+pub const DEFAULT_MAX_ORACLE_DIVERGENCE_BPS: u64 = 500;
+#[constant]
+pub const DEFAULT_MAX_LST_RATE_CHANGE_BPS: u64 = 100;
+// Stake pool rates change once per epoch, a rate refreshed in the previous
+// epoch is still accepted while the pool is being updated
+#[constant]
+pub const MAX_LST_RATE_AGE_EPOCHS: u64 = 1;
 #[constant]
 pub const INTEREST_RATE_SCALE: u64 = 10_000;
 #[constant]
 pub const LIQUIDATION_THRESHOLD_RATE_SCALE: u64 = 10_000;
 #[constant]
 pub const MAX_LTV_RATE_SCALE: u64 = 10_000;
+#[constant]
+pub const LIQUIDATION_BONUS_RATE_SCALE: u64 = 10_000;
+#[constant]
+pub const LIQUIDATION_CLOSE_FACTOR_RATE_SCALE: u64 = 10_000;
+#[constant]
+pub const RESERVE_FACTOR_RATE_SCALE: u64 = 10_000;
+#[constant]
+pub const FLASH_LOAN_FEE_RATE_SCALE: u64 = 10_000;
+#[constant]
+pub const CONFIDENCE_RATE_SCALE: u64 = 10_000;
+#[constant]
+pub const ORACLE_DIVERGENCE_RATE_SCALE: u64 = 10_000;
+#[constant]
+pub const LST_RATE_CHANGE_RATE_SCALE: u64 = 10_000;
+
+#[constant]
+pub const MAX_LIQUIDATION_BONUS: u64 = 2_000;
+#[constant]
+pub const MAX_BORROW_RATE: u64 = 50_000;
+#[constant]
+pub const MAX_FLASH_LOAN_FEE: u64 = 1_000;
 
 #[constant]
-pub const AMOUNT_SCALE: u64 = 1_000_000_000;
+pub const SECONDS_PER_YEAR: u64 = 31_536_000;
+
+// Deposit shares of a bank's first deposit that are never redeemable, fewer
+// for mints with less than 6 decimals
+#[constant]
+pub const LOCKED_DEPOSIT_SHARES: u64 = 1_000;
+
+pub const MAX_POSITIONS: usize = 8;
+
+// Amount passed by `withdraw_all` and `repay_all` to close out the whole
+// share balance at the current exchange rate
+pub const ALL_AMOUNT: u64 = u64::MAX;
+
+pub const MAX_TRANSFER_HOOK_PROGRAMS: usize = 4;
+
+#[constant]
+pub const MAX_MINT_DECIMALS: u8 = 18;
+
+// Bits of `LendingMarket.pause_flags` and `Bank.pause_flags`
+#[constant]
+pub const PAUSE_DEPOSITS: u8 = 1 << 0;
+#[constant]
+pub const PAUSE_BORROWS: u8 = 1 << 1;
+#[constant]
+pub const PAUSE_WITHDRAWALS: u8 = 1 << 2;
+#[constant]
+pub const PAUSE_LIQUIDATIONS: u8 = 1 << 3;
+// Blocks every operation on a bank, or on every bank of the market when set
+// on it, repayments included
+#[constant]
+pub const PAUSE_FROZEN: u8 = 1 << 4;
+#[constant]
+pub const PAUSE_ALL: u8 = PAUSE_DEPOSITS | PAUSE_BORROWS | PAUSE_WITHDRAWALS | PAUSE_LIQUIDATIONS | PAUSE_FROZEN;
 
@@ -19,5 +19,95 @@ pub enum ErrorCode {
     #[msg("Insufficient Collateral")]
     InsufficientCollateral,
     #[msg("Invalid Decimals")]
-    InvalidDecimals
+    InvalidDecimals,
+    #[msg("Collateral and borrowed mint must be different")]
+    SameLiquidationMint,
+    #[msg("Liquidation amount is zero")]
+    InsufficientLiquidationAmount,
+    #[msg("Oracle price must be positive")]
+    InvalidPrice,
+    #[msg("Amount is too small to mint or burn any shares")]
+    ZeroShares,
+    #[msg("User already has the maximum number of positions")]
+    TooManyPositions,
+    #[msg("Remaining accounts do not match the user's positions")]
+    InvalidPositionAccounts,
+    #[msg("Signer is not authorized to perform this action")]
+    Unauthorized,
+    #[msg("Liquidation threshold must be between 1 and 10000 bps")]
+    InvalidLiquidationThreshold,
+    #[msg("Max LTV must be below the liquidation threshold")]
+    InvalidMaxLtv,
+    #[msg("Liquidation bonus is out of bounds")]
+    InvalidLiquidationBonus,
+    #[msg("Liquidation close factor must be between 1 and 10000 bps")]
+    InvalidLiquidationCloseFactor,
+    #[msg("Invalid interest rate model")]
+    InvalidInterestRateModel,
+    #[msg("Invalid price feed id")]
+    InvalidPriceFeedId,
+    #[msg("Deposits are paused")]
+    DepositsPaused,
+    #[msg("Borrows are paused")]
+    BorrowsPaused,
+    #[msg("Withdrawals are paused")]
+    WithdrawalsPaused,
+    #[msg("Liquidations are paused")]
+    LiquidationsPaused,
+    #[msg("Bank is frozen")]
+    BankFrozen,
+    #[msg("Invalid pause flags")]
+    InvalidPauseFlags,
+    #[msg("Deposit would exceed the bank's deposit limit")]
+    DepositLimitExceeded,
+    #[msg("Borrow would exceed the bank's borrow limit")]
+    BorrowLimitExceeded,
+    #[msg("Reserve factor must be at most 10000 bps")]
+    InvalidReserveFactor,
+    #[msg("Not enough liquidity in the bank")]
+    InsufficientLiquidity,
+    #[msg("No protocol fees to collect")]
+    NoFeesToCollect,
+    #[msg("Flash loan fee is out of bounds")]
+    InvalidFlashLoanFee,
+    #[msg("A flash loan is already active on this bank")]
+    FlashLoanActive,
+    #[msg("No active flash loan to repay")]
+    NoActiveFlashLoan,
+    #[msg("Flash borrow must be followed by a flash repay in the same transaction")]
+    MissingFlashRepay,
+    #[msg("Flash loans cannot be taken through CPI")]
+    FlashLoanCpiNotAllowed,
+    #[msg("Oracle confidence interval is too wide")]
+    PriceConfidenceTooWide,
+    #[msg("Invalid oracle settings")]
+    InvalidOracleConfig,
+    #[msg("Oracle account does not match the bank's oracle settings")]
+    InvalidOracleAccount,
+    #[msg("Oracle price is stale")]
+    StalePrice,
+    #[msg("Primary and fallback oracle prices diverge too much")]
+    OracleDivergence,
+    #[msg("Stake pool account does not match the bank")]
+    InvalidStakePool,
+    #[msg("Stake pool has not been updated this epoch")]
+    StakePoolNotUpdated,
+    #[msg("Stake pool exchange rate changed too much since the last refresh")]
+    LstRateChangeTooLarge,
+    #[msg("Stake pool exchange rate must be refreshed")]
+    StaleLstRate,
+    #[msg("Mint has an unsupported Token-2022 extension")]
+    UnsupportedMintExtension,
+    #[msg("Mint is not the native SOL mint")]
+    NotNativeMint,
+    #[msg("User token account still has deposit or borrow shares")]
+    UserTokenAccountNotEmpty,
+    #[msg("User still has open positions")]
+    UserHasOpenPositions,
+    #[msg("First deposit into a bank must exceed the locked deposit shares")]
+    DepositTooSmall,
+    #[msg("Treasury received less than the flash loan plus fee")]
+    FlashLoanUnderRepaid,
+    #[msg("Too many transfer hook programs in the allowlist")]
+    TooManyTransferHookPrograms,
 }
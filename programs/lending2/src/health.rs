@@ -0,0 +1,135 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{LIQUIDATION_THRESHOLD_RATE_SCALE, MAX_LTV_RATE_SCALE};
+use crate::error::ErrorCode;
+use crate::math::Decimal;
+use crate::oracle::get_oracle_price;
+use crate::state::{Bank, User, UserTokenAccount};
+
+/// Remaining accounts per position: `[bank, user_token_account, oracle]`,
+/// followed by the bank's fallback oracle account when it has one. Accounts
+/// after the last position are left for Token-2022 transfer hooks.
+pub const ACCOUNTS_PER_POSITION: usize = 3;
+
+/// A user's positions revalued at current prices
+pub struct Health {
+    pub collateral_value: Decimal,
+    /// Collateral weighted by each collateral bank's `max_ltv`
+    pub borrow_power: Decimal,
+    /// Collateral weighted by each collateral bank's `liquidation_threshold`
+    pub liquidation_value: Decimal,
+    pub borrow_value: Decimal,
+    /// Number of remaining accounts used by the positions
+    pub position_accounts: usize,
+}
+
+impl Health {
+    /// Whether the debt fits within the max LTV of the collateral
+    pub fn is_within_max_ltv(&self) -> bool {
+        self.borrow_value <= self.borrow_power
+    }
+
+    pub fn is_liquidatable(&self) -> bool {
+        !self.borrow_value.is_zero() && self.liquidation_value < self.borrow_value
+    }
+}
+
+/// Revalues every position in `user.positions` from the remaining accounts.
+///
+/// `overrides` holds banks and token accounts already modified in memory by the
+/// calling instruction; they are used instead of the (stale) account data.
+pub fn compute_health(
+    user: &User,
+    remaining_accounts: &[AccountInfo],
+    overrides: &[(&Bank, &UserTokenAccount)],
+    clock: &Clock,
+) -> Result<Health> {
+    let mut health = Health {
+        collateral_value: Decimal::zero(),
+        borrow_power: Decimal::zero(),
+        liquidation_value: Decimal::zero(),
+        borrow_value: Decimal::zero(),
+        position_accounts: 0,
+    };
+
+    let mut remaining = remaining_accounts;
+    for mint in user.positions.iter() {
+        require!(remaining.len() >= ACCOUNTS_PER_POSITION, ErrorCode::InvalidPositionAccounts);
+        let (accounts, rest) = remaining.split_at(ACCOUNTS_PER_POSITION);
+        remaining = rest;
+
+        let (bank, user_token_account) = match overrides
+            .iter()
+            .find(|(bank, _)| bank.mint_address == *mint)
+        {
+            Some((bank, user_token_account)) => ((*bank).clone(), (*user_token_account).clone()),
+            None => {
+                let mut bank = load_account::<Bank>(&accounts[0])?;
+                bank.accrue_interest(clock.unix_timestamp)?;
+                (bank, load_account::<UserTokenAccount>(&accounts[1])?)
+            }
+        };
+
+        require_keys_eq!(bank.lending_market, user.lending_market, ErrorCode::InvalidPositionAccounts);
+        require_keys_eq!(bank.mint_address, *mint, ErrorCode::InvalidPositionAccounts);
+        require_keys_eq!(user_token_account.lending_market, user.lending_market, ErrorCode::InvalidPositionAccounts);
+        require_keys_eq!(user_token_account.owner, user.owner, ErrorCode::InvalidPositionAccounts);
+        require_keys_eq!(user_token_account.mint, *mint, ErrorCode::InvalidPositionAccounts);
+        // The stored fields alone do not pin the account, so check its address too
+        let user_token_account_key = Pubkey::create_program_address(
+            &[
+                b"user-token",
+                user.lending_market.as_ref(),
+                user.owner.as_ref(),
+                mint.as_ref(),
+                &[user_token_account.bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| ErrorCode::InvalidPositionAccounts)?;
+        require_keys_eq!(accounts[1].key(), user_token_account_key, ErrorCode::InvalidPositionAccounts);
+
+        let fallback = if bank.fallback_oracle.is_some() {
+            let (fallback, rest) = remaining.split_first().ok_or(ErrorCode::InvalidPositionAccounts)?;
+            remaining = rest;
+            Some(fallback)
+        } else {
+            None
+        };
+
+        let price = get_oracle_price(&bank, &accounts[2], fallback, clock)?;
+
+        let deposited = bank.deposit_amount_for_shares(user_token_account.deposit_shares)?;
+        let borrowed = bank.borrow_amount_for_shares(user_token_account.borrowed_shares)?;
+
+        // Collateral at the low end and debt at the high end of the confidence interval
+        let collateral_value = Decimal::from_token_amount(deposited, bank.mint_decimals)?
+            .try_mul(price.collateral_price())?;
+
+        health.collateral_value = health.collateral_value.try_add(collateral_value)?;
+        health.borrow_power = health.borrow_power.try_add(
+            collateral_value.try_mul(Decimal::from_ratio(bank.max_ltv, MAX_LTV_RATE_SCALE)?)?,
+        )?;
+        health.liquidation_value = health.liquidation_value.try_add(collateral_value.try_mul(
+            Decimal::from_ratio(bank.liquidation_threshold, LIQUIDATION_THRESHOLD_RATE_SCALE)?,
+        )?)?;
+        health.borrow_value = health.borrow_value.try_add(
+            Decimal::from_token_amount(borrowed, bank.mint_decimals)?.try_mul(price.debt_price())?,
+        )?;
+    }
+
+    health.position_accounts = remaining_accounts.len() - remaining.len();
+
+    msg!("Collateral Value: {}", health.collateral_value);
+    msg!("Borrow Power: {}", health.borrow_power);
+    msg!("Liquidation Value: {}", health.liquidation_value);
+    msg!("Borrow Value: {}", health.borrow_value);
+
+    Ok(health)
+}
+
+fn load_account<T: AccountDeserialize + Owner>(info: &AccountInfo) -> Result<T> {
+    require_keys_eq!(*info.owner, T::owner(), ErrorCode::InvalidPositionAccounts);
+    let data = info.try_borrow_data()?;
+    T::try_deserialize(&mut &data[..])
+}
@@ -2,22 +2,25 @@ use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
     token_interface::{
-        self, 
         Mint, 
         TokenAccount, 
         TokenInterface, 
         TransferChecked
     },
 };
-use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
 
-use crate::constants::{AMOUNT_SCALE, LIQUIDATION_THRESHOLD_RATE_SCALE, MAX_AGE};
+use crate::constants::PAUSE_BORROWS;
 use crate::state::{
     User, 
     Bank, 
+    LendingMarket,
     UserTokenAccount
 };
 use crate::error::ErrorCode;
+use crate::health::compute_health;
+use crate::math::Decimal;
+use crate::oracle::get_oracle_price;
+use crate::transfer_hook;
 
 // Collateral USDC to Borrow SOL / Collateral SOL to Borrow USDC
 #[derive(Accounts)]
@@ -25,32 +28,34 @@ pub struct Borrow<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
+    pub lending_market: Account<'info, LendingMarket>,
+
     pub mint_borrow: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
-        seeds = [b"user", signer.key().as_ref()],
+        seeds = [b"user", lending_market.key().as_ref(), signer.key().as_ref()],
         bump,
     )]
     pub user_account: Account<'info, User>, 
 
     #[account(
         mut,
-        seeds = [mint_borrow.key().as_ref()],
+        seeds = [lending_market.key().as_ref(), mint_borrow.key().as_ref()],
         bump,
     )]
     pub bank_borrow: Account<'info, Bank>,
 
     #[account(
         mut,
-        seeds = [b"treasury", mint_borrow.key().as_ref()],
+        seeds = [b"treasury", lending_market.key().as_ref(), mint_borrow.key().as_ref()],
         bump,
     )]
     pub bank_token_account_borrow: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
-        seeds = [b"user-token", signer.key().as_ref(), mint_borrow.key().as_ref()],
+        seeds = [b"user-token", lending_market.key().as_ref(), signer.key().as_ref(), mint_borrow.key().as_ref()],
         bump,
     )]
     pub user_token_account_borrow: Account<'info, UserTokenAccount>, 
@@ -64,46 +69,85 @@ pub struct Borrow<'info> {
     )]
     pub user_associated_token_account: InterfaceAccount<'info, TokenAccount>, // ATA
 
-    pub price_update: Account<'info, PriceUpdateV2>,
+    /// CHECK: validated against the bank's oracle settings
+    pub price_update: UncheckedAccount<'info>,
+
+    /// CHECK: validated against the bank's fallback oracle, required when it has one
+    pub fallback_price_update: Option<UncheckedAccount<'info>>,
 
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn process_borrow(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+pub fn process_borrow<'info>(ctx: Context<'_, '_, '_, 'info, Borrow<'info>>, amount: u64) -> Result<()> {
     
     let user = & mut ctx.accounts.user_account; 
     let bank_borrow = &mut ctx.accounts.bank_borrow;
     let user_token_account_borrow = &mut ctx.accounts.user_token_account_borrow;
     let price_update = &ctx.accounts.price_update;
 
-    // Scale amount by 10^6 for precision
-    let scaled_amount = amount.checked_mul(AMOUNT_SCALE).unwrap();
-    let actual_price: f64;
-
-    let total_collateral = user.total_deposit_value;
+    bank_borrow.check_not_paused(&ctx.accounts.lending_market, PAUSE_BORROWS, ErrorCode::BorrowsPaused)?;
+    bank_borrow.accrue_interest(Clock::get()?.unix_timestamp)?;
     
-    let price_feed_id = get_feed_id_from_hex(&bank_borrow.price_feed_id)?;
+    let actual_price = get_oracle_price(
+        bank_borrow,
+        &price_update.to_account_info(),
+        ctx.accounts.fallback_price_update.as_ref().map(|account| account.as_ref()),
+        &Clock::get()?,
+    )?.price;
+    let borrow_value = Decimal::from_token_amount(amount, ctx.accounts.mint_borrow.decimals)?
+        .try_mul(actual_price)?;
+
+    msg!("Actual Value: {}", borrow_value);
 
-    let price_data = price_update.get_price_no_older_than(
-            &Clock::get()?,
-            MAX_AGE,
-            &price_feed_id,
-        )?;
+    // Update bank's borrow state
+    let user_shares = bank_borrow.borrow_shares_for_amount(amount)?;
+    // An empty position could never be removed again
+    require!(user_shares > 0, ErrorCode::ZeroShares);
+
+    bank_borrow.total_borrowed = bank_borrow
+        .total_borrowed
+        .checked_add(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    bank_borrow.total_borrowed_shares = bank_borrow
+        .total_borrowed_shares
+        .checked_add(user_shares)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    // Borrows are funded by deposits only, never by the protocol's fees
+    require!(
+        bank_borrow.total_borrowed <= bank_borrow.total_deposits,
+        ErrorCode::InsufficientLiquidity
+    );
+    bank_borrow.check_borrow_limit(actual_price)?;
 
-    actual_price = price_data.price as f64 * 10f64.powi(price_data.exponent);
+    // Update user_token_account's borrow state
+    user_token_account_borrow.borrowed_amount = user_token_account_borrow
+        .borrowed_amount
+        .checked_add(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    user_token_account_borrow.borrowed_shares = user_token_account_borrow
+        .borrowed_shares
+        .checked_add(user_shares)
+        .ok_or(ErrorCode::MathOverflow)?;
+    user_token_account_borrow.last_update = Clock::get()?.unix_timestamp;
 
-    // Calculate borrowable amount
-    let borrowable_amount = total_collateral as f64
-        * (bank_borrow.liquidation_threshold as f64 / LIQUIDATION_THRESHOLD_RATE_SCALE as f64); 
+    user.add_position(ctx.accounts.mint_borrow.key())?;
 
-    msg!("Borrowable Amount: {}", borrowable_amount);
-    msg!("Actual Value: {}", actual_price * amount as f64);
+    // Revalue every position, including this borrow, at current prices
+    let health = compute_health(
+        user,
+        ctx.remaining_accounts,
+        &[(&**bank_borrow, &**user_token_account_borrow)],
+        &Clock::get()?,
+    )?;
 
-    if borrowable_amount < actual_price * amount as f64  {
+    // Total debt must stay within the max LTV of every collateral bank
+    if !health.is_within_max_ltv() {
         return Err(ErrorCode::OverBorrowableAmount.into());
     }
+
     // Transfer borrowed amount to user's ATA
     let transfer_cpi_accounts = TransferChecked {
         from: ctx.accounts.bank_token_account_borrow.to_account_info(),
@@ -114,36 +158,21 @@ pub fn process_borrow(ctx: Context<Borrow>, amount: u64) -> Result<()> {
 
     let cpi_program = ctx.accounts.token_program.to_account_info();
     
+    let lending_market_key = ctx.accounts.lending_market.key();
     let mint_key = ctx.accounts.mint_borrow.key();
     let signer_seeds: &[&[&[u8]]] = &[
         &[
             b"treasury",
+            lending_market_key.as_ref(),
             mint_key.as_ref(),
             &[ctx.bumps.bank_token_account_borrow],
         ]
     ];
     
-    let cpi_ctx = CpiContext::new_with_signer(cpi_program, transfer_cpi_accounts, signer_seeds);
-    token_interface::transfer_checked(cpi_ctx, scaled_amount, ctx.accounts.mint_borrow.decimals)?;
-
-    // Update bank's borrow state
-    if bank_borrow.total_borrowed > 0 {
-        let borrow_ratio = scaled_amount.checked_div(bank_borrow.total_borrowed).unwrap_or(0);
-        let user_shares = bank_borrow.total_borrowed_shares.checked_mul(borrow_ratio).unwrap_or(0);
-    
-        bank_borrow.total_borrowed += scaled_amount;
-        bank_borrow.total_borrowed_shares += user_shares;
-    } else {
-        bank_borrow.total_borrowed = scaled_amount;
-        bank_borrow.total_borrowed_shares = scaled_amount;
-    }
-
-    // Update user_token_account's borrow state
-    user_token_account_borrow.borrowed_amount += scaled_amount;
-    user_token_account_borrow.borrowed_shares += scaled_amount;
-    user_token_account_borrow.last_update = Clock::get()?.unix_timestamp;
-
-    user.total_borrow_value += (actual_price * amount as f64 ) as u64;   
+    // Accounts after the positions are only used by Token-2022 transfer hooks
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, transfer_cpi_accounts, signer_seeds)
+        .with_remaining_accounts(ctx.remaining_accounts[health.position_accounts..].to_vec());
+    transfer_hook::transfer_checked(cpi_ctx, amount, ctx.accounts.mint_borrow.decimals)?;
 
     Ok(())
 }
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::error::ErrorCode;
+use crate::state::{LendingMarket, User, UserTokenAccount};
+
+#[derive(Accounts)]
+pub struct CloseUserTokenAccount<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    pub lending_market: Account<'info, LendingMarket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        close = signer,
+        seeds = [b"user-token", lending_market.key().as_ref(), signer.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub user_token_account: Account<'info, UserTokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct CloseUser<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    pub lending_market: Account<'info, LendingMarket>,
+
+    #[account(
+        mut,
+        close = signer,
+        seeds = [b"user", lending_market.key().as_ref(), signer.key().as_ref()],
+        bump,
+    )]
+    pub user_account: Account<'info, User>,
+}
+
+// Rent goes back to the owner once the account no longer holds any shares
+pub fn process_close_user_token_account(ctx: Context<CloseUserTokenAccount>) -> Result<()> {
+    let user_token_account = &ctx.accounts.user_token_account;
+
+    require!(
+        user_token_account.deposit_shares == 0 && user_token_account.borrowed_shares == 0,
+        ErrorCode::UserTokenAccountNotEmpty
+    );
+
+    Ok(())
+}
+
+pub fn process_close_user(ctx: Context<CloseUser>) -> Result<()> {
+    require!(
+        ctx.accounts.user_account.positions.is_empty(),
+        ErrorCode::UserHasOpenPositions
+    );
+
+    Ok(())
+}
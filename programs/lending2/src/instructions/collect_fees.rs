@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    Mint,
+    TokenAccount,
+    TokenInterface,
+    TransferChecked
+};
+
+use crate::error::ErrorCode;
+use crate::state::{Bank, LendingMarket};
+use crate::transfer_hook;
+
+// Permissionless: fees can only go to the market's fee receiver
+#[derive(Accounts)]
+pub struct CollectFees<'info> {
+    pub lending_market: Account<'info, LendingMarket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [lending_market.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Account<'info, Bank>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", lending_market.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+        constraint = fee_receiver_token_account.owner == lending_market.fee_receiver @ ErrorCode::Unauthorized,
+    )]
+    pub fee_receiver_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn process_collect_fees<'info>(ctx: Context<'_, '_, '_, 'info, CollectFees<'info>>) -> Result<()> {
+    let bank = &mut ctx.accounts.bank;
+
+    bank.accrue_interest(Clock::get()?.unix_timestamp)?;
+
+    // Part of the fees may currently be lent out, collect what is in the treasury
+    let amount = bank
+        .accumulated_protocol_fees
+        .min(ctx.accounts.bank_token_account.amount);
+    require!(amount > 0, ErrorCode::NoFeesToCollect);
+
+    bank.accumulated_protocol_fees -= amount;
+
+    msg!("Collected Fees: {}", amount);
+
+    let transfer_cpi_accounts = TransferChecked {
+        from: ctx.accounts.bank_token_account.to_account_info(),
+        to: ctx.accounts.fee_receiver_token_account.to_account_info(),
+        authority: ctx.accounts.bank_token_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+    };
+
+    let lending_market_key = ctx.accounts.lending_market.key();
+    let mint_key = ctx.accounts.mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"treasury",
+        lending_market_key.as_ref(),
+        mint_key.as_ref(),
+        &[ctx.bumps.bank_token_account],
+    ]];
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        transfer_cpi_accounts,
+        signer_seeds,
+    )
+    // Remaining accounts are only used by Token-2022 transfer hooks
+    .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+    transfer_hook::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+    Ok(())
+}
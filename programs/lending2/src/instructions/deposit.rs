@@ -2,91 +2,102 @@ use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken, 
     token_interface::{
-        self, 
         Mint, 
         TokenAccount, 
         TokenInterface, 
         TransferChecked
     }
 };
-use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
 
-use crate::{constants::{AMOUNT_SCALE, MAX_AGE}, state::{Bank, User, UserTokenAccount}};
+use crate::{
+    constants::PAUSE_DEPOSITS,
+    error::ErrorCode,
+    math::Decimal,
+    oracle::get_oracle_price,
+    state::{Bank, LendingMarket, User, UserTokenAccount},
+    transfer_hook
+};
 
 #[derive(Accounts)]
 pub struct Deposit<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
+    pub lending_market: Account<'info, LendingMarket>,
+
     pub mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         init_if_needed,
         payer = signer,
         space = 8 + User::INIT_SPACE,
-        seeds = [b"user", signer.key().as_ref()],
+        seeds = [b"user", lending_market.key().as_ref(), signer.key().as_ref()],
         bump,
     )]
     pub user_account: Account<'info, User>, 
 
     #[account(
         mut,
-        seeds = [mint.key().as_ref()],
+        seeds = [lending_market.key().as_ref(), mint.key().as_ref()],
         bump,
     )]
     pub bank: Account<'info, Bank>,
 
     #[account(
         mut,
-        seeds = [b"treasury", mint.key().as_ref()],
+        seeds = [b"treasury", lending_market.key().as_ref(), mint.key().as_ref()],
         bump,
     )]
     pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
-        seeds = [b"user-token", signer.key().as_ref(), mint.key().as_ref()],
+        seeds = [b"user-token", lending_market.key().as_ref(), signer.key().as_ref(), mint.key().as_ref()],
         bump,
     )]
     pub user_token_account: Account<'info, UserTokenAccount>, 
 
     #[account(
-        mut,
+        init_if_needed,
+        payer = signer,
         associated_token::mint = mint,
         associated_token::authority = signer,
         associated_token::token_program = token_program,
     )]
     pub user_token_associated_account: InterfaceAccount<'info, TokenAccount>,
 
-    pub price_update: Account<'info, PriceUpdateV2>,
+    /// CHECK: validated against the bank's oracle settings
+    pub price_update: UncheckedAccount<'info>,
+
+    /// CHECK: validated against the bank's fallback oracle, required when it has one
+    pub fallback_price_update: Option<UncheckedAccount<'info>>,
 
     pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
-pub fn process_deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
-    // Scale the deposit amount (e.g., scale up by 10^6)
+// `amount` is in the mint's native base units
+pub fn process_deposit<'info>(ctx: Context<'_, '_, '_, 'info, Deposit<'info>>, amount: u64) -> Result<()> {
 
-    let user = & mut ctx.accounts.user_account; 
     let bank = &mut ctx.accounts.bank;
-    
-    let price_update = &ctx.accounts.price_update;
-    let price_feed_id = get_feed_id_from_hex(&bank.price_feed_id)?;
 
-    let price_data = price_update.get_price_no_older_than(
+    bank.check_not_paused(&ctx.accounts.lending_market, PAUSE_DEPOSITS, ErrorCode::DepositsPaused)?;
+    bank.accrue_interest(Clock::get()?.unix_timestamp)?;
+    
+    let actual_price = get_oracle_price(
+        bank,
+        &ctx.accounts.price_update.to_account_info(),
+        ctx.accounts.fallback_price_update.as_ref().map(|account| account.as_ref()),
         &Clock::get()?,
-        MAX_AGE,
-        &price_feed_id,
-    )?;
-
-    let actual_price = price_data.price as f64 * 10f64.powi(price_data.exponent);
+    )?.price;
 
-    user.total_deposit_value += (actual_price * amount as f64 ) as u64;   
+    let decimals = ctx.accounts.mint.decimals;
+    let deposit_value = Decimal::from_token_amount(amount, decimals)?.try_mul(actual_price)?;
+    msg!("Deposit Value: {}", deposit_value);
 
-    let scaled_amount = amount.checked_mul(AMOUNT_SCALE).unwrap();
-    msg!("Deposit amount: {:?}", scaled_amount);    
-    // Transfer the scaled deposit amount from the user's token account to the bank's treasury
+    msg!("Deposit amount: {:?}", amount);
+    // Transfer the deposit amount from the user's token account to the bank's treasury
     let transfer_cpi_accounts = TransferChecked {
         from: ctx.accounts.user_token_associated_account.to_account_info(),
         to: ctx.accounts.bank_token_account.to_account_info(),
@@ -96,36 +107,65 @@ pub fn process_deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
 
     let cpi_program = ctx.accounts.token_program.to_account_info();
 
-    let cpi_ctx = CpiContext::new(cpi_program, transfer_cpi_accounts);
+    // Remaining accounts are only used by Token-2022 transfer hooks
+    let cpi_ctx = CpiContext::new(cpi_program, transfer_cpi_accounts)
+        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
 
-    let decimals = ctx.accounts.mint.decimals;
-    token_interface::transfer_checked(cpi_ctx, scaled_amount, decimals)?;
+    let balance_before = ctx.accounts.bank_token_account.amount;
+    transfer_hook::transfer_checked(cpi_ctx, amount, decimals)?;
+
+    // Token-2022 transfer fees can make the treasury receive less than `amount`
+    ctx.accounts.bank_token_account.reload()?;
+    let received = ctx
+        .accounts
+        .bank_token_account
+        .amount
+        .checked_sub(balance_before)
+        .ok_or(ErrorCode::MathOverflow)?;
+    msg!("Received amount: {}", received);
 
     // Update the bank's total deposits and shares
     let bank = &mut ctx.accounts.bank;
 
-    if bank.total_deposits == 0 {
-        // Initialize with the scaled deposit amount on the first deposit
-        bank.total_deposits = scaled_amount;
-        bank.total_deposits_shares = scaled_amount;
-    } else {
-        // Only update the total deposits and shares after the first deposit
-        let deposit_ratio = scaled_amount.checked_div(bank.total_deposits).unwrap();
-        let user_share = bank.total_deposits_shares.checked_mul(deposit_ratio).unwrap();
-
-        bank.total_deposits += scaled_amount;
-        bank.total_deposits_shares += user_share;
-    }
+    // Shares are priced before the deposit is added to the pool
+    let shares = bank.deposit_shares_for_amount(received)?;
 
-    // Update the user's token account with the scaled deposit amount and shares
+    // The first deposit locks a few shares for good
+    let locked_shares = if bank.total_deposits_shares == 0 { bank.locked_deposit_shares()? } else { 0 };
+    let user_shares = shares.checked_sub(locked_shares).ok_or(ErrorCode::DepositTooSmall)?;
+    require!(user_shares > 0, ErrorCode::ZeroShares);
+
+    bank.total_deposits = bank
+        .total_deposits
+        .checked_add(received)
+        .ok_or(ErrorCode::MathOverflow)?;
+    bank.total_deposits_shares = bank
+        .total_deposits_shares
+        .checked_add(shares)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    bank.check_deposit_limit(actual_price)?;
+
+    // Update the user's token account with the deposit amount and shares
     let user_token_account = &mut ctx.accounts.user_token_account;
 
     // Update the deposit and deposit shares for the specific token
-    user_token_account.deposit_amount += scaled_amount;
-    user_token_account.deposit_shares += scaled_amount;
+    user_token_account.deposit_amount = user_token_account
+        .deposit_amount
+        .checked_add(received)
+        .ok_or(ErrorCode::MathOverflow)?;
+    user_token_account.deposit_shares = user_token_account
+        .deposit_shares
+        .checked_add(user_shares)
+        .ok_or(ErrorCode::MathOverflow)?;
 
     user_token_account.last_update = Clock::get()?.unix_timestamp;
 
+    let user = &mut ctx.accounts.user_account;
+    user.owner = ctx.accounts.signer.key();
+    user.lending_market = ctx.accounts.lending_market.key();
+    user.add_position(ctx.accounts.mint.key())?;
+
 
 
     Ok(())
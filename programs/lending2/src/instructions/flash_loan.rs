@@ -0,0 +1,214 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use anchor_lang::solana_program::instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT};
+use anchor_lang::solana_program::sysvar::instructions::{
+    self as instructions_sysvar,
+    load_current_index_checked,
+    load_instruction_at_checked
+};
+use anchor_spl::token_interface::{
+    Mint,
+    TokenAccount,
+    TokenInterface,
+    TransferChecked
+};
+
+use crate::constants::{FLASH_LOAN_FEE_RATE_SCALE, PAUSE_BORROWS};
+use crate::error::ErrorCode;
+use crate::math::mul_div_floor;
+use crate::state::{Bank, LendingMarket};
+use crate::transfer_fee::amount_with_transfer_fee;
+use crate::transfer_hook;
+
+/// Position of `bank` in `FlashRepay`, used to match the repay instruction
+const FLASH_REPAY_BANK_INDEX: usize = 3;
+
+// Lends from the treasury for the duration of one transaction. The loan never
+// touches `User` positions, a later `flash_repay` on the same bank must return
+// the amount plus `Bank.flash_loan_fee`.
+#[derive(Accounts)]
+pub struct FlashBorrow<'info> {
+    pub signer: Signer<'info>,
+
+    pub lending_market: Account<'info, LendingMarket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [lending_market.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Account<'info, Bank>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", lending_market.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: address checked against the instructions sysvar
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct FlashRepay<'info> {
+    pub signer: Signer<'info>,
+
+    pub lending_market: Account<'info, LendingMarket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    // Keep at `FLASH_REPAY_BANK_INDEX`
+    #[account(
+        mut,
+        seeds = [lending_market.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Account<'info, Bank>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", lending_market.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = signer,
+        token::token_program = token_program,
+    )]
+    pub source_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn process_flash_borrow<'info>(ctx: Context<'_, '_, '_, 'info, FlashBorrow<'info>>, amount: u64) -> Result<()> {
+    let bank = &mut ctx.accounts.bank;
+
+    bank.check_not_paused(&ctx.accounts.lending_market, PAUSE_BORROWS, ErrorCode::BorrowsPaused)?;
+    require!(bank.flash_loan_amount == 0, ErrorCode::FlashLoanActive);
+    require!(amount > 0, ErrorCode::InsufficientLiquidity);
+
+    // Introspection only sees top level instructions, so a CPI caller could
+    // otherwise hide its own repay check
+    require!(
+        get_stack_height() == TRANSACTION_LEVEL_STACK_HEIGHT,
+        ErrorCode::FlashLoanCpiNotAllowed
+    );
+
+    let available = bank.total_deposits.saturating_sub(bank.total_borrowed);
+    require!(amount <= available, ErrorCode::InsufficientLiquidity);
+
+    // A flash_repay on this bank must follow later in the same transaction
+    let instructions = ctx.accounts.instructions.to_account_info();
+    let current_index = load_current_index_checked(&instructions)? as usize;
+    let bank_key = bank.key();
+    let mut has_repay = false;
+    let mut index = current_index + 1;
+    while let Ok(instruction) = load_instruction_at_checked(index, &instructions) {
+        if instruction.program_id == crate::ID
+            && instruction.data.get(..8) == Some(&crate::instruction::FlashRepay::DISCRIMINATOR[..])
+            && instruction
+                .accounts
+                .get(FLASH_REPAY_BANK_INDEX)
+                .is_some_and(|account| account.pubkey == bank_key)
+        {
+            has_repay = true;
+            break;
+        }
+        index += 1;
+    }
+    require!(has_repay, ErrorCode::MissingFlashRepay);
+
+    bank.flash_loan_amount = amount;
+
+    msg!("Flash Borrow Amount: {}", amount);
+
+    let transfer_cpi_accounts = TransferChecked {
+        from: ctx.accounts.bank_token_account.to_account_info(),
+        to: ctx.accounts.destination_token_account.to_account_info(),
+        authority: ctx.accounts.bank_token_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+    };
+
+    let lending_market_key = ctx.accounts.lending_market.key();
+    let mint_key = ctx.accounts.mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"treasury",
+        lending_market_key.as_ref(),
+        mint_key.as_ref(),
+        &[ctx.bumps.bank_token_account],
+    ]];
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        transfer_cpi_accounts,
+        signer_seeds,
+    )
+    // Remaining accounts are only used by Token-2022 transfer hooks
+    .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+    transfer_hook::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+    Ok(())
+}
+
+pub fn process_flash_repay<'info>(ctx: Context<'_, '_, '_, 'info, FlashRepay<'info>>) -> Result<()> {
+    let bank = &mut ctx.accounts.bank;
+
+    let amount = bank.flash_loan_amount;
+    require!(amount > 0, ErrorCode::NoActiveFlashLoan);
+
+    // Rounded down, so tiny loans pay nothing rather than inflating the share price
+    let fee = mul_div_floor(amount, bank.flash_loan_fee, FLASH_LOAN_FEE_RATE_SCALE)?;
+    let repay_amount = amount.checked_add(fee).ok_or(ErrorCode::MathOverflow)?;
+
+    // The fee is shared between depositors and the protocol like interest
+    bank.credit_earnings(fee)?;
+    bank.flash_loan_amount = 0;
+
+    msg!("Flash Repay Amount: {}", repay_amount);
+
+    let transfer_cpi_accounts = TransferChecked {
+        from: ctx.accounts.source_token_account.to_account_info(),
+        to: ctx.accounts.bank_token_account.to_account_info(),
+        authority: ctx.accounts.signer.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+    };
+
+    // Token-2022 transfer fees are paid on top, the treasury must receive `repay_amount`
+    let transfer_amount = amount_with_transfer_fee(
+        &ctx.accounts.mint.to_account_info(),
+        repay_amount,
+        &Clock::get()?,
+    )?;
+
+    let balance_before = ctx.accounts.bank_token_account.amount;
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer_cpi_accounts)
+        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+    transfer_hook::transfer_checked(cpi_ctx, transfer_amount, ctx.accounts.mint.decimals)?;
+
+    ctx.accounts.bank_token_account.reload()?;
+    let received = ctx
+        .accounts
+        .bank_token_account
+        .amount
+        .checked_sub(balance_before)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(received >= repay_amount, ErrorCode::FlashLoanUnderRepaid);
+
+    Ok(())
+}
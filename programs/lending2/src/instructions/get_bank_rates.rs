@@ -0,0 +1,19 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Bank, BankRates};
+
+#[derive(Accounts)]
+pub struct GetBankRates<'info> {
+    pub bank: Account<'info, Bank>,
+}
+
+// Read-only: returns the bank's utilization, borrow APR and supply APR
+pub fn process_get_bank_rates(ctx: Context<GetBankRates>) -> Result<BankRates> {
+    let rates = ctx.accounts.bank.rates()?;
+
+    msg!("Utilization: {}", rates.utilization);
+    msg!("Borrow Rate: {}", rates.borrow_rate);
+    msg!("Supply Rate: {}", rates.supply_rate);
+
+    Ok(rates)
+}
@@ -1,24 +1,61 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        confidential_transfer::ConfidentialTransferMint,
+        transfer_hook::TransferHook,
+        BaseStateWithExtensions,
+        ExtensionType,
+        StateWithExtensions
+    },
+};
 use anchor_spl::token_interface::{
     Mint, 
     TokenAccount, 
     TokenInterface
 };
 
-use crate::{constants::INTEREST_RATE_SCALE, state::Bank};
+use crate::{
+    constants::{
+        DEFAULT_MAX_CONFIDENCE_BPS,
+        DEFAULT_MAX_LST_RATE_CHANGE_BPS,
+        DEFAULT_MAX_ORACLE_DIVERGENCE_BPS,
+        DEFAULT_MAX_PRICE_AGE,
+        MAX_MINT_DECIMALS
+    },
+    error::ErrorCode,
+    state::{Bank, InterestRateModel, LendingMarket, OracleSetup},
+};
+
+/// Risk and oracle settings of a new bank. `price_feed_id` may be empty for
+/// banks that do not use Pyth.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct InitBankArgs {
+    pub liquidation_threshold: u64,
+    pub max_ltv: u64,
+    pub liquidation_bonus: u64,
+    pub liquidation_close_factor: u64,
+    pub interest_rate_model: InterestRateModel,
+    pub oracle_setup: OracleSetup,
+    pub oracle: Pubkey,
+    pub price_feed_id: String,
+}
 
 #[derive(Accounts)] 
 pub struct InitBank<'info> {
     #[account(mut)]
-    pub signer: Signer<'info>,
+    pub owner: Signer<'info>,
+
+    #[account(has_one = owner @ ErrorCode::Unauthorized)]
+    pub lending_market: Account<'info, LendingMarket>,
 
     pub mint: InterfaceAccount<'info, Mint>,   
 
     #[account(
         init,
-        payer = signer,
+        payer = owner,
         space = 8 + Bank::INIT_SPACE,
-        seeds = [mint.key().as_ref()],
+        seeds = [lending_market.key().as_ref(), mint.key().as_ref()],
         bump,
     )]
     pub bank: Account<'info, Bank>,
@@ -27,8 +64,8 @@ pub struct InitBank<'info> {
         init,
         token::mint = mint,
         token::authority = bank_token_account,
-        payer = signer,
-        seeds = [b"treasury", mint.key().as_ref()],
+        payer = owner,
+        seeds = [b"treasury", lending_market.key().as_ref(), mint.key().as_ref()],
         bump,
     )]
     pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
@@ -37,18 +74,78 @@ pub struct InitBank<'info> {
     pub system_program: Program<'info, System>,
 }
 
-pub fn process_init_bank(ctx: Context<InitBank>,
-    liquidation_threshold: u64,
-    max_ltv: u64,
-    price_feed_id: String,
-) -> Result<()> {
+pub fn process_init_bank(ctx: Context<InitBank>, args: InitBankArgs) -> Result<()> {
+
+    let decimals = ctx.accounts.mint.decimals;
+    require!(decimals <= MAX_MINT_DECIMALS, ErrorCode::InvalidDecimals);
+
+    let mint_info = ctx.accounts.mint.to_account_info();
+    if *mint_info.owner == spl_token_2022::ID {
+        check_mint_extensions(&mint_info, &ctx.accounts.lending_market.transfer_hook_allowlist)?;
+    }
 
     let bank = &mut ctx.accounts.bank;
     bank.mint_address = ctx.accounts.mint.key();
-    bank.authority = ctx.accounts.signer.key();
-    bank.liquidation_threshold = liquidation_threshold;
-    bank.max_ltv = max_ltv;
-    bank.interest_rate = (0.05 * INTEREST_RATE_SCALE as f64) as u64;
-    bank.price_feed_id = price_feed_id;
+    bank.mint_decimals = decimals;
+    bank.lending_market = ctx.accounts.lending_market.key();
+    bank.liquidation_threshold = args.liquidation_threshold;
+    bank.max_ltv = args.max_ltv;
+    bank.liquidation_bonus = args.liquidation_bonus;
+    bank.liquidation_close_factor = args.liquidation_close_factor;
+    bank.interest_rate_model = args.interest_rate_model;
+    bank.oracle_setup = args.oracle_setup;
+    bank.oracle = args.oracle;
+    bank.price_feed_id = Bank::parse_price_feed_id(&args.price_feed_id)?;
+    bank.max_price_age = DEFAULT_MAX_PRICE_AGE;
+    bank.max_confidence_bps = DEFAULT_MAX_CONFIDENCE_BPS;
+    bank.max_oracle_divergence_bps = DEFAULT_MAX_ORACLE_DIVERGENCE_BPS;
+    bank.max_lst_rate_change_bps = DEFAULT_MAX_LST_RATE_CHANGE_BPS;
+    bank.last_updated = Clock::get()?.unix_timestamp;
+
+    bank.validate_config()?;
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Rejects Token-2022 mints whose extensions break the pool's accounting:
+/// tokens that can be moved out of the treasury, cannot be moved at all,
+/// are meant to be held confidentially, or run code the market did not allow
+fn check_mint_extensions(mint: &AccountInfo, transfer_hook_allowlist: &[Pubkey]) -> Result<()> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+
+    for extension in mint.get_extension_types()? {
+        match extension {
+            ExtensionType::PermanentDelegate | ExtensionType::NonTransferable => {
+                return Err(ErrorCode::UnsupportedMintExtension.into());
+            }
+            // The pool only sees public balances. Confidential transfers are
+            // fine while optional, i.e. auto-approved for every account; mints
+            // gating them behind the authority's approval are confidential-only
+            ExtensionType::ConfidentialTransferMint => {
+                let confidential_transfer = mint.get_extension::<ConfidentialTransferMint>()?;
+                require!(
+                    bool::from(confidential_transfer.auto_approve_new_accounts),
+                    ErrorCode::UnsupportedMintExtension
+                );
+            }
+            // The hook program must be allowlisted by the market, and fixed:
+            // an authority could later swap it for any other program
+            ExtensionType::TransferHook => {
+                let transfer_hook = mint.get_extension::<TransferHook>()?;
+                require!(
+                    Option::<Pubkey>::from(transfer_hook.authority).is_none(),
+                    ErrorCode::UnsupportedMintExtension
+                );
+                if let Some(program_id) = Option::<Pubkey>::from(transfer_hook.program_id) {
+                    require!(
+                        transfer_hook_allowlist.contains(&program_id),
+                        ErrorCode::UnsupportedMintExtension
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
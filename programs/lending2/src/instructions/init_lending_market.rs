@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+
+use crate::state::LendingMarket;
+
+#[derive(Accounts)]
+pub struct InitLendingMarket<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    // A fresh keypair per market, so one deployment can host several
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + LendingMarket::INIT_SPACE,
+    )]
+    pub lending_market: Account<'info, LendingMarket>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn process_init_lending_market(
+    ctx: Context<InitLendingMarket>,
+    guardian: Pubkey,
+    fee_receiver: Pubkey,
+) -> Result<()> {
+    let lending_market = &mut ctx.accounts.lending_market;
+    lending_market.owner = ctx.accounts.owner.key();
+    lending_market.guardian = guardian;
+    lending_market.fee_receiver = fee_receiver;
+
+    msg!("Lending Market: {}", lending_market.key());
+
+    Ok(())
+}
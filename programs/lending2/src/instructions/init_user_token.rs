@@ -1,19 +1,21 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token_interface::Mint;
-use crate::state::UserTokenAccount;
+use crate::state::{LendingMarket, UserTokenAccount};
 
 #[derive(Accounts)]
 pub struct InitUserTokenAccount<'info> {
     #[account(mut)]
     pub signer: Signer<'info>, 
 
+    pub lending_market: Account<'info, LendingMarket>,
+
     pub mint: InterfaceAccount<'info, Mint>,   
 
     #[account(
         init,
         payer = signer,
         space = 8 + UserTokenAccount::INIT_SPACE,
-        seeds = [b"user-token", signer.key().as_ref(), mint.key().as_ref()],
+        seeds = [b"user-token", lending_market.key().as_ref(), signer.key().as_ref(), mint.key().as_ref()],
         bump,
     )]
     pub user_token_account: Account<'info, UserTokenAccount>, // PDA for the user-token account
@@ -21,11 +23,12 @@ pub struct InitUserTokenAccount<'info> {
     pub system_program: Program<'info, System>, // System program for account initialization
 }
 
-pub fn process_init_user_token_account(ctx: Context<InitUserTokenAccount>, name: String, mint: Pubkey,) -> Result<()> {
+pub fn process_init_user_token_account(ctx: Context<InitUserTokenAccount>, name: String) -> Result<()> {
     let user_token_account = &mut ctx.accounts.user_token_account;
 
     user_token_account.owner = ctx.accounts.signer.key(); // User's address
-    user_token_account.mint = mint.key();    
+    user_token_account.lending_market = ctx.accounts.lending_market.key();
+    user_token_account.mint = ctx.accounts.mint.key();
     user_token_account.name = name;
     user_token_account.bump = ctx.bumps.user_token_account;
     
@@ -1,16 +1,337 @@
-// use anchor_lang::prelude::*;
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        Mint,
+        TokenAccount,
+        TokenInterface,
+        TransferChecked
+    },
+};
 
-// #[derive(Accounts)]
-// pub struct Liquidate<'info> {
-//     #[account(mut)]
-//     pub liquidator: Signer<'info>,
+use crate::constants::{
+    LIQUIDATION_BONUS_RATE_SCALE,
+    LIQUIDATION_CLOSE_FACTOR_RATE_SCALE,
+    PAUSE_LIQUIDATIONS
+};
+use crate::state::{Bank, LendingMarket, User, UserTokenAccount};
+use crate::error::ErrorCode;
+use crate::health::compute_health;
+use crate::math::Decimal;
+use crate::oracle::get_oracle_price;
+use crate::transfer_hook;
 
-//     pub system_program: Program<'info, System>,
-// }
+// Liquidator repays part of the borrower's debt in `borrowed_mint`
+// and receives the same value (plus bonus) of `collateral_mint`
+#[derive(Accounts)]
+pub struct Liquidate<'info> {
+    #[account(mut)]
+    pub liquidator: Signer<'info>,
 
-// pub fn process_liquidate(ctx: Context<Liquidate>) -> Result<()> {
-//     // Accounts
-    
+    /// CHECK: only used as a seed to derive the borrower's accounts
+    pub borrower: UncheckedAccount<'info>,
 
-//     Ok(())
-// }
+    pub lending_market: Account<'info, LendingMarket>,
+
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    pub borrowed_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"user", lending_market.key().as_ref(), borrower.key().as_ref()],
+        bump,
+    )]
+    pub user_account: Account<'info, User>,
+
+    #[account(
+        mut,
+        seeds = [lending_market.key().as_ref(), collateral_mint.key().as_ref()],
+        bump,
+    )]
+    pub collateral_bank: Account<'info, Bank>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", lending_market.key().as_ref(), collateral_mint.key().as_ref()],
+        bump,
+    )]
+    pub collateral_bank_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [lending_market.key().as_ref(), borrowed_mint.key().as_ref()],
+        bump,
+    )]
+    pub borrowed_bank: Account<'info, Bank>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", lending_market.key().as_ref(), borrowed_mint.key().as_ref()],
+        bump,
+    )]
+    pub borrowed_bank_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"user-token", lending_market.key().as_ref(), borrower.key().as_ref(), collateral_mint.key().as_ref()],
+        bump,
+    )]
+    pub user_collateral_token_account: Account<'info, UserTokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"user-token", lending_market.key().as_ref(), borrower.key().as_ref(), borrowed_mint.key().as_ref()],
+        bump,
+    )]
+    pub user_borrowed_token_account: Account<'info, UserTokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = liquidator,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = liquidator,
+        associated_token::token_program = token_program,
+    )]
+    pub liquidator_collateral_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = borrowed_mint,
+        associated_token::authority = liquidator,
+        associated_token::token_program = token_program,
+    )]
+    pub liquidator_borrowed_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: validated against the bank's oracle settings
+    pub price_update_collateral: UncheckedAccount<'info>,
+
+    /// CHECK: validated against the bank's oracle settings
+    pub price_update_borrowed: UncheckedAccount<'info>,
+
+    /// CHECK: validated against the collateral bank's fallback oracle, required when it has one
+    pub fallback_price_update_collateral: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: validated against the borrowed bank's fallback oracle, required when it has one
+    pub fallback_price_update_borrowed: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn process_liquidate<'info>(ctx: Context<'_, '_, '_, 'info, Liquidate<'info>>, amount: u64) -> Result<()> {
+
+    require_keys_neq!(
+        ctx.accounts.collateral_mint.key(),
+        ctx.accounts.borrowed_mint.key(),
+        ErrorCode::SameLiquidationMint
+    );
+
+    let lending_market = &ctx.accounts.lending_market;
+    ctx.accounts.collateral_bank.check_not_paused(lending_market, PAUSE_LIQUIDATIONS, ErrorCode::LiquidationsPaused)?;
+    ctx.accounts.borrowed_bank.check_not_paused(lending_market, PAUSE_LIQUIDATIONS, ErrorCode::LiquidationsPaused)?;
+
+    let clock = Clock::get()?;
+
+    ctx.accounts.collateral_bank.accrue_interest(clock.unix_timestamp)?;
+    ctx.accounts.borrowed_bank.accrue_interest(clock.unix_timestamp)?;
+
+    let collateral_bank = &ctx.accounts.collateral_bank;
+    let borrowed_bank = &ctx.accounts.borrowed_bank;
+    let user_collateral_token_account = &ctx.accounts.user_collateral_token_account;
+    let user_borrowed_token_account = &ctx.accounts.user_borrowed_token_account;
+
+    // The seized amount is priced at the mid price, only health is conservative
+    let collateral_price = get_oracle_price(
+        collateral_bank,
+        &ctx.accounts.price_update_collateral.to_account_info(),
+        ctx.accounts.fallback_price_update_collateral.as_ref().map(|account| account.as_ref()),
+        &clock,
+    )?.price;
+    let borrowed_price = get_oracle_price(
+        borrowed_bank,
+        &ctx.accounts.price_update_borrowed.to_account_info(),
+        ctx.accounts.fallback_price_update_borrowed.as_ref().map(|account| account.as_ref()),
+        &clock,
+    )?.price;
+
+    // Revalue every position of the borrower at the current price
+    let health = compute_health(
+        &ctx.accounts.user_account,
+        ctx.remaining_accounts,
+        &[
+            (collateral_bank, user_collateral_token_account),
+            (borrowed_bank, user_borrowed_token_account),
+        ],
+        &clock,
+    )?;
+
+    // Debt above the threshold-weighted collateral of every bank can be liquidated
+    require!(
+        health.is_liquidatable(),
+        ErrorCode::NotUnderCollateralized
+    );
+
+    let collateral_decimals = ctx.accounts.collateral_mint.decimals;
+    let borrowed_decimals = ctx.accounts.borrowed_mint.decimals;
+
+    let collateral_amount = collateral_bank.deposit_amount_for_shares(user_collateral_token_account.deposit_shares)?;
+    let borrowed_amount = borrowed_bank.borrow_amount_for_shares(user_borrowed_token_account.borrowed_shares)?;
+
+    // The close factor caps how much of the debt can be repaid at once
+    let max_repay_amount = Decimal::from(borrowed_amount)
+        .try_mul(Decimal::from_ratio(
+            borrowed_bank.liquidation_close_factor,
+            LIQUIDATION_CLOSE_FACTOR_RATE_SCALE,
+        )?)?
+        .try_floor_u64()?;
+
+    let bonus_multiplier = Decimal::one().try_add(Decimal::from_ratio(
+        collateral_bank.liquidation_bonus,
+        LIQUIDATION_BONUS_RATE_SCALE,
+    )?)?;
+
+    // Only collateral that is not lent out can be seized, the protocol's fees
+    // stay in the treasury
+    let seizable_amount = collateral_amount
+        .min(collateral_bank.total_deposits.saturating_sub(collateral_bank.total_borrowed));
+    require!(seizable_amount > 0, ErrorCode::InsufficientLiquidity);
+
+    // Repaying more than the seizable collateral can pay for (with bonus) is
+    // never charged to the liquidator
+    let max_repay_for_collateral = Decimal::from_token_amount(seizable_amount, collateral_decimals)?
+        .try_mul(collateral_price)?
+        .try_div(bonus_multiplier)?
+        .try_div(borrowed_price)?
+        .try_floor_token_amount(borrowed_decimals)?;
+
+    let repay_amount = amount
+        .min(max_repay_amount)
+        .min(max_repay_for_collateral);
+
+    require!(repay_amount > 0, ErrorCode::InsufficientLiquidationAmount);
+
+    // Liquidator repays the debt into the borrowed bank's treasury
+    let repay_cpi_accounts = TransferChecked {
+        from: ctx.accounts.liquidator_borrowed_token_account.to_account_info(),
+        to: ctx.accounts.borrowed_bank_token_account.to_account_info(),
+        authority: ctx.accounts.liquidator.to_account_info(),
+        mint: ctx.accounts.borrowed_mint.to_account_info(),
+    };
+    // Accounts after the positions are only used by Token-2022 transfer hooks,
+    // of either mint
+    let hook_accounts = ctx.remaining_accounts[health.position_accounts..].to_vec();
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), repay_cpi_accounts)
+        .with_remaining_accounts(hook_accounts.clone());
+
+    let balance_before = ctx.accounts.borrowed_bank_token_account.amount;
+    transfer_hook::transfer_checked(cpi_ctx, repay_amount, borrowed_decimals)?;
+
+    // Only what the treasury received after Token-2022 transfer fees repays debt
+    ctx.accounts.borrowed_bank_token_account.reload()?;
+    let repaid = ctx
+        .accounts
+        .borrowed_bank_token_account
+        .amount
+        .checked_sub(balance_before)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(repaid > 0, ErrorCode::InsufficientLiquidationAmount);
+
+    let repay_value = Decimal::from_token_amount(repaid, borrowed_decimals)?.try_mul(borrowed_price)?;
+    let seize_value = repay_value.try_mul(bonus_multiplier)?;
+
+    // Rounding must never seize more collateral than is seizable
+    let seize_amount = seize_value
+        .try_div(collateral_price)?
+        .try_floor_token_amount(collateral_decimals)?
+        .min(seizable_amount);
+
+    msg!("Repay Amount: {}", repaid);
+    msg!("Seize Amount: {}", seize_amount);
+
+    // Collateral treasury pays the liquidator
+    let seize_cpi_accounts = TransferChecked {
+        from: ctx.accounts.collateral_bank_token_account.to_account_info(),
+        to: ctx.accounts.liquidator_collateral_token_account.to_account_info(),
+        authority: ctx.accounts.collateral_bank_token_account.to_account_info(),
+        mint: ctx.accounts.collateral_mint.to_account_info(),
+    };
+
+    let lending_market_key = ctx.accounts.lending_market.key();
+    let collateral_mint_key = ctx.accounts.collateral_mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[
+        &[
+            b"treasury",
+            lending_market_key.as_ref(),
+            collateral_mint_key.as_ref(),
+            &[ctx.bumps.collateral_bank_token_account],
+        ]
+    ];
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        seize_cpi_accounts,
+        signer_seeds,
+    )
+    .with_remaining_accounts(hook_accounts);
+    transfer_hook::transfer_checked(cpi_ctx, seize_amount, collateral_decimals)?;
+
+    // Update the borrowed side
+    let borrowed_bank = &mut ctx.accounts.borrowed_bank;
+    let user_borrowed_token_account = &mut ctx.accounts.user_borrowed_token_account;
+
+    let repay_shares = borrowed_bank
+        .borrow_shares_to_repay(repaid)?
+        .min(user_borrowed_token_account.borrowed_shares);
+
+    user_borrowed_token_account.borrowed_amount = user_borrowed_token_account
+        .borrowed_amount
+        .saturating_sub(repaid);
+    user_borrowed_token_account.borrowed_shares = user_borrowed_token_account
+        .borrowed_shares
+        .checked_sub(repay_shares)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    borrowed_bank.total_borrowed = borrowed_bank
+        .total_borrowed
+        .saturating_sub(repaid);
+    borrowed_bank.total_borrowed_shares = borrowed_bank
+        .total_borrowed_shares
+        .checked_sub(repay_shares)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    // Update the collateral side
+    let collateral_bank = &mut ctx.accounts.collateral_bank;
+    let user_collateral_token_account = &mut ctx.accounts.user_collateral_token_account;
+
+    // Seizing the whole balance burns every remaining share
+    let seize_shares = if seize_amount == collateral_amount {
+        user_collateral_token_account.deposit_shares
+    } else {
+        collateral_bank.deposit_shares_to_withdraw(seize_amount)?
+    };
+
+    user_collateral_token_account.deposit_amount = user_collateral_token_account
+        .deposit_amount
+        .saturating_sub(seize_amount);
+    user_collateral_token_account.deposit_shares = user_collateral_token_account
+        .deposit_shares
+        .checked_sub(seize_shares)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    collateral_bank.total_deposits = collateral_bank
+        .total_deposits
+        .checked_sub(seize_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    collateral_bank.total_deposits_shares = collateral_bank
+        .total_deposits_shares
+        .checked_sub(seize_shares)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let user = &mut ctx.accounts.user_account;
+    user.refresh_position(&ctx.accounts.user_collateral_token_account);
+    user.refresh_position(&ctx.accounts.user_borrowed_token_account);
+
+    Ok(())
+}
@@ -3,6 +3,18 @@
 pub use init_user_token::*;
 pub mod init_user_token;
 
+pub use close_user::*;
+pub mod close_user;
+
+pub use init_lending_market::*;
+pub mod init_lending_market;
+
+pub use update_lending_market::*;
+pub mod update_lending_market;
+
+pub use set_pause_flags::*;
+pub mod set_pause_flags;
+
 pub use init_bank::*;
 pub mod init_bank;
 
@@ -16,4 +28,25 @@ pub use withdraw::*;
 pub mod withdraw;
 
 pub use repay::*;
-pub mod repay;
\ No newline at end of file
+pub mod repay;
+
+pub use liquidate::*;
+pub mod liquidate;
+
+pub use get_bank_rates::*;
+pub mod get_bank_rates;
+
+pub use update_bank_config::*;
+pub mod update_bank_config;
+
+pub use collect_fees::*;
+pub mod collect_fees;
+
+pub use flash_loan::*;
+pub mod flash_loan;
+
+pub use refresh_lst_rate::*;
+pub mod refresh_lst_rate;
+
+pub use native_sol::*;
+pub mod native_sol;
\ No newline at end of file
@@ -0,0 +1,240 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::system_program::{self, CreateAccount, Transfer};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_interface::{
+    self,
+    CloseAccount,
+    InitializeAccount3,
+    SyncNative,
+    TransferChecked
+};
+
+use crate::error::ErrorCode;
+// Glob imports also bring in the code generated for the nested `Accounts`
+use crate::instructions::{borrow::*, deposit::*, repay::*, withdraw::*};
+
+// The SOL variants reuse the regular instructions on the native mint's bank,
+// going through the signer's wSOL associated token account. Only the lamports
+// moved by the instruction are wrapped or unwrapped: wSOL the signer already
+// held stays in that account, which is closed only when it ends up empty.
+
+#[derive(Accounts)]
+pub struct WithdrawSol<'info> {
+    pub withdraw: Withdraw<'info>,
+
+    // New keypair, created and closed within the instruction to unwrap the
+    // withdrawn amount
+    #[account(mut)]
+    pub temporary_wsol_account: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BorrowSol<'info> {
+    pub borrow: Borrow<'info>,
+
+    // New keypair, created and closed within the instruction to unwrap the
+    // borrowed amount
+    #[account(mut)]
+    pub temporary_wsol_account: Signer<'info>,
+}
+
+/// Accounts needed to move SOL in and out of the signer's wSOL account
+struct NativeSolAccounts<'info> {
+    signer: AccountInfo<'info>,
+    wsol_account: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+}
+
+pub fn process_deposit_sol<'info>(ctx: Context<'_, '_, '_, 'info, Deposit<'info>>, amount: u64) -> Result<()> {
+    check_native_mint(&ctx.accounts.mint.key())?;
+
+    let accounts = NativeSolAccounts {
+        signer: ctx.accounts.signer.to_account_info(),
+        wsol_account: ctx.accounts.user_token_associated_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info(),
+        system_program: ctx.accounts.system_program.to_account_info(),
+    };
+    let balance_before = ctx.accounts.user_token_associated_account.amount;
+
+    wrap_sol(&accounts, amount)?;
+
+    // Moves exactly `amount` out again
+    process_deposit(ctx, amount)?;
+
+    if balance_before == 0 {
+        close_wsol_account(&accounts, &accounts.wsol_account)?;
+    }
+    Ok(())
+}
+
+pub fn process_repay_sol<'info>(ctx: Context<'_, '_, '_, 'info, Repay<'info>>, amount: u64) -> Result<()> {
+    check_native_mint(&ctx.accounts.mint.key())?;
+
+    let accounts = NativeSolAccounts {
+        signer: ctx.accounts.signer.to_account_info(),
+        wsol_account: ctx.accounts.user_associated_token_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info(),
+        system_program: ctx.accounts.system_program.to_account_info(),
+    };
+    let balance_before = ctx.accounts.user_associated_token_account.amount;
+
+    wrap_sol(&accounts, amount)?;
+
+    // Moves exactly `amount` out again
+    process_repay(ctx, amount)?;
+
+    if balance_before == 0 {
+        close_wsol_account(&accounts, &accounts.wsol_account)?;
+    }
+    Ok(())
+}
+
+pub fn process_withdraw_sol<'info>(
+    ctx: Context<'_, '_, '_, 'info, WithdrawSol<'info>>,
+    amount: u64,
+) -> Result<()> {
+    check_native_mint(&ctx.accounts.withdraw.mint.key())?;
+
+    let withdraw = &ctx.accounts.withdraw;
+    let accounts = NativeSolAccounts {
+        signer: withdraw.signer.to_account_info(),
+        wsol_account: withdraw.user_associated_token_account.to_account_info(),
+        mint: withdraw.mint.to_account_info(),
+        token_program: withdraw.token_program.to_account_info(),
+        system_program: withdraw.system_program.to_account_info(),
+    };
+    let temporary_wsol_account = ctx.accounts.temporary_wsol_account.to_account_info();
+    let balance_before = withdraw.user_associated_token_account.amount;
+    let decimals = withdraw.mint.decimals;
+
+    process_withdraw(
+        Context::new(ctx.program_id, &mut ctx.accounts.withdraw, ctx.remaining_accounts, ctx.bumps.withdraw),
+        amount,
+    )?;
+
+    unwrap_received_sol(&accounts, &temporary_wsol_account, balance_before, decimals)
+}
+
+pub fn process_borrow_sol<'info>(
+    ctx: Context<'_, '_, '_, 'info, BorrowSol<'info>>,
+    amount: u64,
+) -> Result<()> {
+    check_native_mint(&ctx.accounts.borrow.mint_borrow.key())?;
+
+    let borrow = &ctx.accounts.borrow;
+    let accounts = NativeSolAccounts {
+        signer: borrow.signer.to_account_info(),
+        wsol_account: borrow.user_associated_token_account.to_account_info(),
+        mint: borrow.mint_borrow.to_account_info(),
+        token_program: borrow.token_program.to_account_info(),
+        system_program: borrow.system_program.to_account_info(),
+    };
+    let temporary_wsol_account = ctx.accounts.temporary_wsol_account.to_account_info();
+    let balance_before = borrow.user_associated_token_account.amount;
+    let decimals = borrow.mint_borrow.decimals;
+
+    process_borrow(
+        Context::new(ctx.program_id, &mut ctx.accounts.borrow, ctx.remaining_accounts, ctx.bumps.borrow),
+        amount,
+    )?;
+
+    unwrap_received_sol(&accounts, &temporary_wsol_account, balance_before, decimals)
+}
+
+fn check_native_mint(mint: &Pubkey) -> Result<()> {
+    require!(
+        *mint == spl_token::native_mint::ID || *mint == spl_token_2022::native_mint::ID,
+        ErrorCode::NotNativeMint
+    );
+    Ok(())
+}
+
+// Moves lamports into the wSOL account and syncs its token balance
+fn wrap_sol(accounts: &NativeSolAccounts, amount: u64) -> Result<()> {
+    let transfer_cpi_accounts = Transfer {
+        from: accounts.signer.clone(),
+        to: accounts.wsol_account.clone(),
+    };
+    system_program::transfer(
+        CpiContext::new(accounts.system_program.clone(), transfer_cpi_accounts),
+        amount,
+    )?;
+
+    let sync_cpi_accounts = SyncNative {
+        account: accounts.wsol_account.clone(),
+    };
+    token_interface::sync_native(CpiContext::new(accounts.token_program.clone(), sync_cpi_accounts))
+}
+
+// Unwraps the wSOL received since `balance_before` through a temporary account,
+// leaving the earlier balance in the signer's wSOL account
+fn unwrap_received_sol<'info>(
+    accounts: &NativeSolAccounts<'info>,
+    temporary_wsol_account: &AccountInfo<'info>,
+    balance_before: u64,
+    decimals: u8,
+) -> Result<()> {
+    if balance_before == 0 {
+        return close_wsol_account(accounts, &accounts.wsol_account);
+    }
+
+    let balance_after = {
+        let data = accounts.wsol_account.try_borrow_data()?;
+        token_interface::TokenAccount::try_deserialize(&mut &data[..])?.amount
+    };
+    let received = balance_after
+        .checked_sub(balance_before)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let space = spl_token::state::Account::LEN;
+    let create_cpi_accounts = CreateAccount {
+        from: accounts.signer.clone(),
+        to: temporary_wsol_account.clone(),
+    };
+    system_program::create_account(
+        CpiContext::new(accounts.system_program.clone(), create_cpi_accounts),
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        accounts.token_program.key,
+    )?;
+
+    let initialize_cpi_accounts = InitializeAccount3 {
+        account: temporary_wsol_account.clone(),
+        mint: accounts.mint.clone(),
+        authority: accounts.signer.clone(),
+    };
+    token_interface::initialize_account3(CpiContext::new(
+        accounts.token_program.clone(),
+        initialize_cpi_accounts,
+    ))?;
+
+    let transfer_cpi_accounts = TransferChecked {
+        from: accounts.wsol_account.clone(),
+        to: temporary_wsol_account.clone(),
+        authority: accounts.signer.clone(),
+        mint: accounts.mint.clone(),
+    };
+    token_interface::transfer_checked(
+        CpiContext::new(accounts.token_program.clone(), transfer_cpi_accounts),
+        received,
+        decimals,
+    )?;
+
+    close_wsol_account(accounts, temporary_wsol_account)
+}
+
+// Closes a wSOL account, returning its balance and rent to the signer as lamports
+fn close_wsol_account<'info>(accounts: &NativeSolAccounts<'info>, wsol_account: &AccountInfo<'info>) -> Result<()> {
+    let close_cpi_accounts = CloseAccount {
+        account: wsol_account.clone(),
+        destination: accounts.signer.clone(),
+        authority: accounts.signer.clone(),
+    };
+    token_interface::close_account(CpiContext::new(accounts.token_program.clone(), close_cpi_accounts))
+}
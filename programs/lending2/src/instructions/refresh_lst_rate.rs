@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::error::ErrorCode;
+use crate::oracle::stake_pool::refresh_exchange_rate;
+use crate::state::{Bank, LendingMarket, OracleSetup};
+
+// Permissionless: anyone can push the stake pool's current rate to the bank
+#[derive(Accounts)]
+pub struct RefreshLstRate<'info> {
+    pub lending_market: Account<'info, LendingMarket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [lending_market.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Account<'info, Bank>,
+
+    /// CHECK: must be the stake pool configured on the bank, parsed in the handler
+    pub stake_pool: UncheckedAccount<'info>,
+}
+
+pub fn process_refresh_lst_rate(ctx: Context<RefreshLstRate>) -> Result<()> {
+    let bank = &mut ctx.accounts.bank;
+
+    let OracleSetup::StakePool { stake_pool } = bank.oracle_setup else {
+        return Err(ErrorCode::InvalidOracleConfig.into());
+    };
+    require_keys_eq!(ctx.accounts.stake_pool.key(), stake_pool, ErrorCode::InvalidStakePool);
+
+    refresh_exchange_rate(bank, &ctx.accounts.stake_pool, &Clock::get()?)
+}
@@ -2,16 +2,17 @@ use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
     token_interface::{
-       self,
        Mint, 
        TokenAccount, 
        TokenInterface, 
        TransferChecked
    }};
-use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
 
-use crate::{constants::{AMOUNT_SCALE, MAX_AGE}, state::{Bank, User, UserTokenAccount}};
+use crate::constants::ALL_AMOUNT;
+use crate::{state::{Bank, LendingMarket, User, UserTokenAccount}};
 use crate::error::ErrorCode;
+use crate::transfer_fee::amount_with_transfer_fee;
+use crate::transfer_hook;
 
 #[derive(Accounts)]
 pub struct Repay<'info> {
@@ -19,106 +20,132 @@ pub struct Repay<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
+    pub lending_market: Account<'info, LendingMarket>,
+
     pub mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
-        seeds = [mint.key().as_ref()],
+        seeds = [lending_market.key().as_ref(), mint.key().as_ref()],
         bump,
     )]
     pub bank: Account<'info, Bank>,
 
     #[account(
         mut,
-        seeds = [b"treasury", mint.key().as_ref()],
+        seeds = [b"treasury", lending_market.key().as_ref(), mint.key().as_ref()],
         bump,
     )]
     pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
-        seeds = [b"user", signer.key().as_ref()],
+        seeds = [b"user", lending_market.key().as_ref(), signer.key().as_ref()],
         bump,
     )]
     pub user_account: Account<'info, User>, 
  
     #[account(
         mut,
-        seeds = [b"user-token", signer.key().as_ref(), mint.key().as_ref()],
+        seeds = [b"user-token", lending_market.key().as_ref(), signer.key().as_ref(), mint.key().as_ref()],
         bump,
     )]
     pub user_token_account: Account<'info, UserTokenAccount>, 
 
     #[account(
-        mut,
+        init_if_needed,
+        payer = signer,
         associated_token::mint = mint,
         associated_token::authority = signer,
         associated_token::token_program = token_program,
     )]
     pub user_associated_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    pub price_update: Account<'info, PriceUpdateV2>,
-    
+
     pub token_program: Interface<'info, TokenInterface>,    
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn process_repay(ctx: Context<Repay>, amount: u64) -> Result<()> {
+pub fn process_repay<'info>(ctx: Context<'_, '_, '_, 'info, Repay<'info>>, amount: u64) -> Result<()> {
 
-    let user_account = &mut ctx.accounts.user_account;
     let bank = &mut ctx.accounts.bank;
-    let user_token_account = &mut ctx.accounts.user_token_account;
-    let price_update = &ctx.accounts.price_update;
-    let scaled_amount = amount.checked_mul(AMOUNT_SCALE).unwrap();
 
-    let price_feed_id = get_feed_id_from_hex(&bank.price_feed_id)?;
+    // Repaying only lowers risk, so it stays open unless the bank is frozen
+    bank.check_not_frozen(&ctx.accounts.lending_market)?;
+    bank.accrue_interest(Clock::get()?.unix_timestamp)?;
+
+    // Debt includes the interest accrued on the user's borrow shares
+    let borrowed = bank.borrow_amount_for_shares(ctx.accounts.user_token_account.borrowed_shares)?;
+    let repay_all = amount == ALL_AMOUNT;
+    let amount = if repay_all { borrowed } else { amount };
+
+    // No price is needed: repaying must work even when the oracle is down
+    msg!("Repay Amount: {}", amount);
+
+    // Repaying everything covers the Token-2022 transfer fee on top of the debt
+    let transfer_amount = if repay_all {
+        amount_with_transfer_fee(&ctx.accounts.mint.to_account_info(), amount, &Clock::get()?)?
+    } else {
+        amount
+    };
+
+    let balance_before = ctx.accounts.bank_token_account.amount;
+    transfer_tokens(&ctx, transfer_amount)?;
 
-    let price_data = price_update.get_price_no_older_than(
-        &Clock::get()?,
-        MAX_AGE,
-        &price_feed_id,
-    )?;
-    let token_price = price_data.price as f64 * 10f64.powi(price_data.exponent);
-    msg!("Token Price: {}", token_price);
+    // Token-2022 transfer fees can make the treasury receive less than `amount`
+    ctx.accounts.bank_token_account.reload()?;
+    let received = ctx
+        .accounts
+        .bank_token_account
+        .amount
+        .checked_sub(balance_before)
+        .ok_or(ErrorCode::MathOverflow)?;
+    msg!("Received Amount: {}", received);
+
+    // The fee is rounded up, so a full repay may arrive slightly above the debt
+    let received = if repay_all { received.min(borrowed) } else { received };
 
-    let repay_value = (amount as f64 * token_price).round() as u64;
-    msg!("Withdrawal Value: {}", repay_value);
+    let user_account = &mut ctx.accounts.user_account;
+    let bank = &mut ctx.accounts.bank;
+    let user_token_account = &mut ctx.accounts.user_token_account;
 
     require!(
-        user_token_account.borrowed_amount >= amount,
+        borrowed >= received,
         ErrorCode::OverRepay
     );
+
+    // Repaying the whole debt burns every remaining share so no dust is left
+    let repay_shares = if received == borrowed {
+        user_token_account.borrowed_shares
+    } else {
+        bank.borrow_shares_to_repay(received)?
+    };
+    require!(repay_shares > 0, ErrorCode::ZeroShares);
     
-    // Perform all state updates first
-    msg!("Borrowed Amount: {}", user_token_account.borrowed_amount);
+    msg!("Borrowed Amount: {}", borrowed);
     user_token_account.borrowed_amount = user_token_account
         .borrowed_amount
-        .checked_sub(scaled_amount)
-        .ok_or(ErrorCode::MathOverflow)?;
+        .saturating_sub(received);
     user_token_account.borrowed_shares = user_token_account
         .borrowed_shares
-        .checked_sub(scaled_amount)
-        .ok_or(ErrorCode::MathOverflow)?;
-    user_account.total_borrow_value = user_account
-        .total_borrow_value
-        .checked_sub(repay_value)
+        .checked_sub(repay_shares)
         .ok_or(ErrorCode::MathOverflow)?;
+    // Rounding up each borrower's debt can leave it a few units above the pool total
     bank.total_borrowed = bank
         .total_borrowed
-        .checked_sub(scaled_amount)
-        .ok_or(ErrorCode::MathOverflow)?;
+        .saturating_sub(received);
     bank.total_borrowed_shares = bank
         .total_borrowed_shares
-        .checked_sub(scaled_amount)
+        .checked_sub(repay_shares)
         .ok_or(ErrorCode::MathOverflow)?;
 
-    transfer_tokens(ctx, scaled_amount)?;
+    user_account.refresh_position(user_token_account);
 
     Ok(())   
 }
 
-fn transfer_tokens(ctx: Context<Repay>, amount: u64) -> Result<()> {
+fn transfer_tokens<'info>(ctx: &Context<'_, '_, '_, 'info, Repay<'info>>, amount: u64) -> Result<()> {
     
     let transfer_cpi_accounts = TransferChecked {
         from: ctx.accounts.user_associated_token_account.to_account_info(),
@@ -129,14 +156,16 @@ fn transfer_tokens(ctx: Context<Repay>, amount: u64) -> Result<()> {
 
     let cpi_program = ctx.accounts.token_program.to_account_info();
 
+    // Remaining accounts are only used by Token-2022 transfer hooks
     let cpi_ctx = CpiContext::new(
         cpi_program,
         transfer_cpi_accounts,
-    );
+    )
+    .with_remaining_accounts(ctx.remaining_accounts.to_vec());
 
     let decimals = ctx.accounts.mint.decimals;
 
-    token_interface::transfer_checked(cpi_ctx, amount, decimals)?;
+    transfer_hook::transfer_checked(cpi_ctx, amount, decimals)?;
 
     Ok(())
 }
\ No newline at end of file
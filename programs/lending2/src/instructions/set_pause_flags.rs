@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::state::{Bank, LendingMarket};
+
+// Signed by the market owner or guardian, checked in the handlers
+#[derive(Accounts)]
+pub struct SetMarketPauseFlags<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub lending_market: Account<'info, LendingMarket>,
+}
+
+#[derive(Accounts)]
+pub struct SetBankPauseFlags<'info> {
+    pub authority: Signer<'info>,
+
+    pub lending_market: Account<'info, LendingMarket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [lending_market.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Account<'info, Bank>,
+}
+
+pub fn process_set_market_pause_flags(ctx: Context<SetMarketPauseFlags>, pause_flags: u8) -> Result<()> {
+    let lending_market = &mut ctx.accounts.lending_market;
+
+    lending_market.check_pause_authority(
+        &ctx.accounts.authority.key(),
+        lending_market.pause_flags,
+        pause_flags,
+    )?;
+    lending_market.pause_flags = pause_flags;
+
+    msg!("Market Pause Flags: {:#07b}", pause_flags);
+
+    Ok(())
+}
+
+pub fn process_set_bank_pause_flags(ctx: Context<SetBankPauseFlags>, pause_flags: u8) -> Result<()> {
+    let bank = &mut ctx.accounts.bank;
+
+    ctx.accounts.lending_market.check_pause_authority(
+        &ctx.accounts.authority.key(),
+        bank.pause_flags,
+        pause_flags,
+    )?;
+    bank.pause_flags = pause_flags;
+
+    msg!("Bank Pause Flags: {:#07b}", pause_flags);
+
+    Ok(())
+}
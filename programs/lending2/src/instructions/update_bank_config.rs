@@ -0,0 +1,152 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::error::ErrorCode;
+use crate::state::{Bank, InterestRateModel, LendingMarket, OracleSetup, OracleSource, PriceMode};
+
+/// Fallback oracle settings, `price_feed_id` may be empty for non-Pyth oracles
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct FallbackOracleArgs {
+    pub setup: OracleSetup,
+    pub oracle: Pubkey,
+    pub price_feed_id: String,
+}
+
+/// Fields left as `None` keep their current value
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct BankConfigArgs {
+    pub liquidation_threshold: Option<u64>,
+    pub max_ltv: Option<u64>,
+    pub liquidation_bonus: Option<u64>,
+    pub liquidation_close_factor: Option<u64>,
+    pub interest_rate_model: Option<InterestRateModel>,
+    pub price_feed_id: Option<String>,
+    pub deposit_limit: Option<u64>,
+    pub borrow_limit: Option<u64>,
+    pub deposit_limit_usd: Option<u64>,
+    pub borrow_limit_usd: Option<u64>,
+    pub reserve_factor: Option<u64>,
+    pub flash_loan_fee: Option<u64>,
+    pub max_price_age: Option<u64>,
+    pub max_confidence_bps: Option<u64>,
+    pub price_mode: Option<PriceMode>,
+    pub oracle_setup: Option<OracleSetup>,
+    pub oracle: Option<Pubkey>,
+    pub fallback_oracle: Option<FallbackOracleArgs>,
+    pub remove_fallback_oracle: Option<bool>,
+    pub max_oracle_divergence_bps: Option<u64>,
+    pub max_lst_rate_change_bps: Option<u64>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateBankConfig<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(has_one = owner @ ErrorCode::Unauthorized)]
+    pub lending_market: Account<'info, LendingMarket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [lending_market.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Account<'info, Bank>,
+}
+
+pub fn process_update_bank_config(ctx: Context<UpdateBankConfig>, args: BankConfigArgs) -> Result<()> {
+    let bank = &mut ctx.accounts.bank;
+
+    // Settle interest at the old rate before the model can change
+    bank.accrue_interest(Clock::get()?.unix_timestamp)?;
+
+    if let Some(liquidation_threshold) = args.liquidation_threshold {
+        bank.liquidation_threshold = liquidation_threshold;
+    }
+    if let Some(max_ltv) = args.max_ltv {
+        bank.max_ltv = max_ltv;
+    }
+    if let Some(liquidation_bonus) = args.liquidation_bonus {
+        bank.liquidation_bonus = liquidation_bonus;
+    }
+    if let Some(liquidation_close_factor) = args.liquidation_close_factor {
+        bank.liquidation_close_factor = liquidation_close_factor;
+    }
+    if let Some(interest_rate_model) = args.interest_rate_model {
+        bank.interest_rate_model = interest_rate_model;
+    }
+    if let Some(price_feed_id) = args.price_feed_id {
+        bank.price_feed_id = Bank::parse_price_feed_id(&price_feed_id)?;
+    }
+
+    if let Some(deposit_limit) = args.deposit_limit {
+        bank.deposit_limit = deposit_limit;
+    }
+    if let Some(borrow_limit) = args.borrow_limit {
+        bank.borrow_limit = borrow_limit;
+    }
+    if let Some(deposit_limit_usd) = args.deposit_limit_usd {
+        bank.deposit_limit_usd = deposit_limit_usd;
+    }
+    if let Some(borrow_limit_usd) = args.borrow_limit_usd {
+        bank.borrow_limit_usd = borrow_limit_usd;
+    }
+    if let Some(reserve_factor) = args.reserve_factor {
+        bank.reserve_factor = reserve_factor;
+    }
+    if let Some(flash_loan_fee) = args.flash_loan_fee {
+        bank.flash_loan_fee = flash_loan_fee;
+    }
+    if let Some(max_price_age) = args.max_price_age {
+        bank.max_price_age = max_price_age;
+    }
+    if let Some(max_confidence_bps) = args.max_confidence_bps {
+        bank.max_confidence_bps = max_confidence_bps;
+    }
+    if let Some(price_mode) = args.price_mode {
+        bank.price_mode = price_mode;
+    }
+    if let Some(oracle_setup) = args.oracle_setup {
+        // A rate read from another stake pool must not bound or price the new one
+        if oracle_setup != bank.oracle_setup {
+            bank.lst_exchange_rate = 0;
+            bank.lst_rate_updated_epoch = 0;
+        }
+        bank.oracle_setup = oracle_setup;
+    }
+    if let Some(oracle) = args.oracle {
+        bank.oracle = oracle;
+    }
+    if let Some(fallback_oracle) = args.fallback_oracle {
+        bank.fallback_oracle = Some(OracleSource {
+            setup: fallback_oracle.setup,
+            oracle: fallback_oracle.oracle,
+            price_feed_id: Bank::parse_price_feed_id(&fallback_oracle.price_feed_id)?,
+        });
+    }
+    if args.remove_fallback_oracle == Some(true) {
+        bank.fallback_oracle = None;
+    }
+    if let Some(max_oracle_divergence_bps) = args.max_oracle_divergence_bps {
+        bank.max_oracle_divergence_bps = max_oracle_divergence_bps;
+    }
+    if let Some(max_lst_rate_change_bps) = args.max_lst_rate_change_bps {
+        bank.max_lst_rate_change_bps = max_lst_rate_change_bps;
+    }
+
+    bank.validate_config()?;
+
+    msg!("Liquidation Threshold: {}", bank.liquidation_threshold);
+    msg!("Max LTV: {}", bank.max_ltv);
+    msg!("Liquidation Bonus: {}", bank.liquidation_bonus);
+    msg!("Liquidation Close Factor: {}", bank.liquidation_close_factor);
+    msg!("Reserve Factor: {}", bank.reserve_factor);
+    msg!("Flash Loan Fee: {}", bank.flash_loan_fee);
+    msg!("Max Price Age: {}", bank.max_price_age);
+    msg!("Max Confidence: {}", bank.max_confidence_bps);
+    msg!("Deposit Limit: {} (USD {})", bank.deposit_limit, bank.deposit_limit_usd);
+    msg!("Borrow Limit: {} (USD {})", bank.borrow_limit, bank.borrow_limit_usd);
+
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_TRANSFER_HOOK_PROGRAMS;
+use crate::error::ErrorCode;
+use crate::state::LendingMarket;
+
+/// Fields left as `None` keep their current value
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct LendingMarketArgs {
+    pub owner: Option<Pubkey>,
+    pub guardian: Option<Pubkey>,
+    pub fee_receiver: Option<Pubkey>,
+    pub transfer_hook_allowlist: Option<Vec<Pubkey>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateLendingMarket<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner @ ErrorCode::Unauthorized,
+    )]
+    pub lending_market: Account<'info, LendingMarket>,
+}
+
+pub fn process_update_lending_market(ctx: Context<UpdateLendingMarket>, args: LendingMarketArgs) -> Result<()> {
+    let lending_market = &mut ctx.accounts.lending_market;
+
+    if let Some(owner) = args.owner {
+        lending_market.owner = owner;
+    }
+    if let Some(guardian) = args.guardian {
+        lending_market.guardian = guardian;
+    }
+    if let Some(fee_receiver) = args.fee_receiver {
+        lending_market.fee_receiver = fee_receiver;
+    }
+
+    if let Some(transfer_hook_allowlist) = args.transfer_hook_allowlist {
+        require!(
+            transfer_hook_allowlist.len() <= MAX_TRANSFER_HOOK_PROGRAMS,
+            ErrorCode::TooManyTransferHookPrograms
+        );
+        lending_market.transfer_hook_allowlist = transfer_hook_allowlist;
+    }
+
+    msg!("Owner: {}", lending_market.owner);
+    msg!("Guardian: {}", lending_market.guardian);
+    msg!("Fee Receiver: {}", lending_market.fee_receiver);
+
+    Ok(())
+}
@@ -4,50 +4,54 @@ use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
     token_interface::{
-        self, 
         Mint, 
         TokenAccount, 
         TokenInterface, 
         TransferChecked
     },
 };
-use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
 
-use crate::constants::{AMOUNT_SCALE, MAX_AGE, MAX_LTV_RATE_SCALE};
+use crate::constants::{ALL_AMOUNT, PAUSE_WITHDRAWALS};
 use crate::{error::ErrorCode, state::UserTokenAccount};
-use crate::state::{Bank, User};
+use crate::health::compute_health;
+use crate::math::Decimal;
+use crate::oracle::get_oracle_price;
+use crate::state::{Bank, LendingMarket, User};
+use crate::transfer_hook;
 
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
+    pub lending_market: Account<'info, LendingMarket>,
+
     pub mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
-        seeds = [b"user", signer.key().as_ref()],
+        seeds = [b"user", lending_market.key().as_ref(), signer.key().as_ref()],
         bump,
     )]
     pub user_account: Account<'info, User>, 
 
     #[account(
         mut,
-        seeds = [b"user-token", signer.key().as_ref(), mint.key().as_ref()],
+        seeds = [b"user-token", lending_market.key().as_ref(), signer.key().as_ref(), mint.key().as_ref()],
         bump,
     )]
     pub user_token_account: Account<'info, UserTokenAccount>, 
 
     #[account(
         mut,
-        seeds = [mint.key().as_ref()],
+        seeds = [lending_market.key().as_ref(), mint.key().as_ref()],
         bump,
     )]
     pub bank: Account<'info, Bank>,
 
     #[account(
         mut,
-        seeds = [b"treasury", mint.key().as_ref()],
+        seeds = [b"treasury", lending_market.key().as_ref(), mint.key().as_ref()],
         bump,
     )]
     pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
@@ -61,97 +65,105 @@ pub struct Withdraw<'info> {
     )]
     pub user_associated_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    pub price_update: Account<'info, PriceUpdateV2>,
+    /// CHECK: validated against the bank's oracle settings
+    pub price_update: UncheckedAccount<'info>,
+
+    /// CHECK: validated against the bank's fallback oracle, required when it has one
+    pub fallback_price_update: Option<UncheckedAccount<'info>>,
     
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn process_withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+pub fn process_withdraw<'info>(ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>, amount: u64) -> Result<()> {
     
     let user_token_account = &mut ctx.accounts.user_token_account;
     let user_account= &mut ctx.accounts.user_account;
     let bank = &mut ctx.accounts.bank;
     let price_update = &ctx.accounts.price_update;
-    
-    let price_feed_id = get_feed_id_from_hex(&bank.price_feed_id)?;
 
-    let price_data = price_update.get_price_no_older_than(
+    bank.check_not_paused(&ctx.accounts.lending_market, PAUSE_WITHDRAWALS, ErrorCode::WithdrawalsPaused)?;
+    bank.accrue_interest(Clock::get()?.unix_timestamp)?;
+    
+    let token_price = get_oracle_price(
+        bank,
+        &price_update.to_account_info(),
+        ctx.accounts.fallback_price_update.as_ref().map(|account| account.as_ref()),
         &Clock::get()?,
-        MAX_AGE,
-        &price_feed_id,           
-    )?;
-
-    let token_price = price_data.price as f64 * 10f64.powi(price_data.exponent);
+    )?.price;
     msg!("Token Price: {}", token_price);
 
-    // Convert withdrawal amount to its USD value
-
-    let withdrawal_value = (amount as f64 * token_price).round() as u64;
-
-    msg!("Withdrawal Value: {}", withdrawal_value);
-
-    // Ensure user has enough deposit to withdraw
+    // Ensure user has enough deposit (including earned interest) to withdraw
+    let deposited = bank.deposit_amount_for_shares(user_token_account.deposit_shares)?;
+    let amount = if amount == ALL_AMOUNT { deposited } else { amount };
     require!(
-        user_token_account.deposit_amount >= amount,
+        deposited >= amount,
         ErrorCode::InsufficientCollateral
     );
 
-    // Calculate user's remaining collateral value after withdrawal
-    let remaining_collateral_value = user_account
-        .total_deposit_value
-        .checked_sub(withdrawal_value)
-        .ok_or(ErrorCode::MathOverflow)?;
+    // Convert withdrawal amount to its USD value
 
-    msg!("Remaining Collateral Value: {}", remaining_collateral_value);
+    let withdrawal_value = Decimal::from_token_amount(amount, ctx.accounts.mint.decimals)?
+        .try_mul(token_price)?;
 
-    msg!("Max LTV: {}", bank.max_ltv);
+    msg!("Withdrawal Value: {}", withdrawal_value);
 
-    // Ensure the remaining collateral meets the max LTV requirement
-    let max_allowed_borrow_value = remaining_collateral_value
-        .checked_mul(bank.max_ltv)
-        .ok_or_else(|| ErrorCode::MathOverflow)?
-        .checked_div(MAX_LTV_RATE_SCALE)
-        .ok_or_else(|| ErrorCode::MathOverflow)?;
+    // Withdrawing the whole balance burns every remaining share
+    let withdraw_shares = if amount == deposited {
+        user_token_account.deposit_shares
+    } else {
+        bank.deposit_shares_to_withdraw(amount)?
+    };
+    require!(withdraw_shares > 0, ErrorCode::ZeroShares);
 
-    msg!("Max Allowed Borrow Value: {}", max_allowed_borrow_value);
-    
+    // Only liquidity that is not lent out can be withdrawn, the protocol's fees stay in the treasury
     require!(
-        user_account.total_borrow_value <= max_allowed_borrow_value,
-        ErrorCode::ExceedsMaxLTV
+        bank.total_deposits.saturating_sub(bank.total_borrowed) >= amount,
+        ErrorCode::InsufficientLiquidity
     );
 
     // Perform all state updates first
     user_token_account.deposit_amount = user_token_account
         .deposit_amount
-        .checked_sub(amount * AMOUNT_SCALE)
-        .ok_or(ErrorCode::MathOverflow)?;
+        .saturating_sub(amount);
     user_token_account.deposit_shares = user_token_account
         .deposit_shares
-        .checked_sub(amount * AMOUNT_SCALE)
-        .ok_or(ErrorCode::MathOverflow)?;
-    user_account.total_deposit_value = user_account
-        .total_deposit_value
-        .checked_sub(withdrawal_value)
+        .checked_sub(withdraw_shares)
         .ok_or(ErrorCode::MathOverflow)?;
     bank.total_deposits = bank
         .total_deposits
-        .checked_sub(amount * AMOUNT_SCALE)
+        .checked_sub(amount)
         .ok_or(ErrorCode::MathOverflow)?;
     bank.total_deposits_shares = bank
         .total_deposits_shares
-        .checked_sub(amount * AMOUNT_SCALE)
+        .checked_sub(withdraw_shares)
         .ok_or(ErrorCode::MathOverflow)?;
-    
+
+    // Revalue every position after the withdrawal at current prices
+    let health = compute_health(
+        user_account,
+        ctx.remaining_accounts,
+        &[(&**bank, &**user_token_account)],
+        &Clock::get()?,
+    )?;
+
+    // Ensure the remaining collateral meets the max LTV requirement
+    require!(
+        health.is_within_max_ltv(),
+        ErrorCode::ExceedsMaxLTV
+    );
+
+    user_account.refresh_position(user_token_account);
 
     // Now call `transfer_tokens` after all mutable borrows are finished
-    transfer_tokens(ctx, amount * AMOUNT_SCALE)?;
+    transfer_tokens(ctx, amount, health.position_accounts)?;
 
     Ok(())
 }
 
-fn transfer_tokens(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+// Accounts after the first `position_accounts` are only used by Token-2022 transfer hooks
+fn transfer_tokens<'info>(ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>, amount: u64, position_accounts: usize) -> Result<()> {
     let transfer_cpi_accounts = TransferChecked {
         from: ctx.accounts.bank_token_account.to_account_info(),
         to: ctx.accounts.user_associated_token_account.to_account_info(),
@@ -159,10 +171,12 @@ fn transfer_tokens(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
         mint: ctx.accounts.mint.to_account_info(),
     };
 
+    let lending_market_key = ctx.accounts.lending_market.key();
     let mint_key = ctx.accounts.mint.key();
 
     let signer_seeds: &[&[&[u8]]] = &[&[
         b"treasury",
+        lending_market_key.as_ref(),
         mint_key.as_ref(),
         &[ctx.bumps.bank_token_account],
     ]];
@@ -171,11 +185,12 @@ fn transfer_tokens(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
         ctx.accounts.token_program.to_account_info(),
         transfer_cpi_accounts,
         signer_seeds,
-    );
+    )
+    .with_remaining_accounts(ctx.remaining_accounts[position_accounts..].to_vec());
 
     let decimals = ctx.accounts.mint.decimals;
 
-    token_interface::transfer_checked(cpi_ctx, amount, decimals)?;
+    transfer_hook::transfer_checked(cpi_ctx, amount, decimals)?;
 
     Ok(())
 }
\ No newline at end of file
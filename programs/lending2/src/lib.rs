@@ -3,51 +3,132 @@ use anchor_lang::prelude::*;
 declare_id!("AjnXUaDfPD88JyARjMkYaCDnpbWuGiRZvHdvKfQbGZnt");
 
 use instructions::*;
+use state::BankRates;
+use constants::ALL_AMOUNT;
 mod state;
 mod instructions;
 mod error;  
 mod constants;
+mod math;
+mod health;
+mod oracle;
+mod transfer_fee;
+mod transfer_hook;
 
 
 #[program]
 pub mod lending2 {
     use super::*;
 
+    pub fn init_lending_market(
+        ctx: Context<InitLendingMarket>,
+        guardian: Pubkey,
+        fee_receiver: Pubkey,
+    ) -> Result<()> {
+        process_init_lending_market(ctx, guardian, fee_receiver)
+    }
+
+    pub fn update_lending_market(ctx: Context<UpdateLendingMarket>, args: LendingMarketArgs) -> Result<()> {
+        process_update_lending_market(ctx, args)
+    }
+
+    pub fn set_market_pause_flags(ctx: Context<SetMarketPauseFlags>, pause_flags: u8) -> Result<()> {
+        process_set_market_pause_flags(ctx, pause_flags)
+    }
+
+    pub fn set_bank_pause_flags(ctx: Context<SetBankPauseFlags>, pause_flags: u8) -> Result<()> {
+        process_set_bank_pause_flags(ctx, pause_flags)
+    }
+
     pub fn init_user_token_account(
          ctx: Context<InitUserTokenAccount>,
          name: String,
-         mint_address: Pubkey
         ) -> Result<()> {
         msg!("Start Init User Token Account");
-        process_init_user_token_account(ctx, name, mint_address)
+        process_init_user_token_account(ctx, name)
     }
 
-    pub fn init_bank(
-        ctx: Context<InitBank>,
-        liquidation_threshold: u64,
-        max_ltv: u64,
-        price_feed_id: String,
-    ) -> Result<()> {
-        process_init_bank(ctx, liquidation_threshold, max_ltv, price_feed_id)
+    pub fn close_user_token_account(ctx: Context<CloseUserTokenAccount>) -> Result<()> {
+        process_close_user_token_account(ctx)
     }
 
+    pub fn close_user(ctx: Context<CloseUser>) -> Result<()> {
+        process_close_user(ctx)
+    }
+
+    pub fn init_bank(ctx: Context<InitBank>, args: InitBankArgs) -> Result<()> {
+        process_init_bank(ctx, args)
+    }
+
+    pub fn update_bank_config(ctx: Context<UpdateBankConfig>, args: BankConfigArgs) -> Result<()> {
+        process_update_bank_config(ctx, args)
+    }
 
-    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+
+    pub fn refresh_lst_rate(ctx: Context<RefreshLstRate>) -> Result<()> {
+        process_refresh_lst_rate(ctx)
+    }
+
+    pub fn deposit<'info>(ctx: Context<'_, '_, '_, 'info, Deposit<'info>>, amount: u64) -> Result<()> {
         process_deposit(ctx, amount)
     }
 
-    pub fn borrow(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+    pub fn borrow<'info>(ctx: Context<'_, '_, '_, 'info, Borrow<'info>>, amount: u64) -> Result<()> {
         process_borrow(ctx, amount)
     }
 
-    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+    pub fn withdraw<'info>(ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>, amount: u64) -> Result<()> {
         process_withdraw(ctx, amount)
     }
 
-    pub fn repay(ctx: Context<Repay>, amount: u64) -> Result<()> {
+    pub fn repay<'info>(ctx: Context<'_, '_, '_, 'info, Repay<'info>>, amount: u64) -> Result<()> {
         process_repay(ctx, amount)
     }
 
+    pub fn withdraw_all<'info>(ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>) -> Result<()> {
+        process_withdraw(ctx, ALL_AMOUNT)
+    }
+
+    pub fn repay_all<'info>(ctx: Context<'_, '_, '_, 'info, Repay<'info>>) -> Result<()> {
+        process_repay(ctx, ALL_AMOUNT)
+    }
+
+    pub fn liquidate<'info>(ctx: Context<'_, '_, '_, 'info, Liquidate<'info>>, amount: u64) -> Result<()> {
+        process_liquidate(ctx, amount)
+    }
+
+    pub fn deposit_sol<'info>(ctx: Context<'_, '_, '_, 'info, Deposit<'info>>, amount: u64) -> Result<()> {
+        process_deposit_sol(ctx, amount)
+    }
+
+    pub fn borrow_sol<'info>(ctx: Context<'_, '_, '_, 'info, BorrowSol<'info>>, amount: u64) -> Result<()> {
+        process_borrow_sol(ctx, amount)
+    }
+
+    pub fn withdraw_sol<'info>(ctx: Context<'_, '_, '_, 'info, WithdrawSol<'info>>, amount: u64) -> Result<()> {
+        process_withdraw_sol(ctx, amount)
+    }
+
+    pub fn repay_sol<'info>(ctx: Context<'_, '_, '_, 'info, Repay<'info>>, amount: u64) -> Result<()> {
+        process_repay_sol(ctx, amount)
+    }
+
+    pub fn collect_fees<'info>(ctx: Context<'_, '_, '_, 'info, CollectFees<'info>>) -> Result<()> {
+        process_collect_fees(ctx)
+    }
+
+    pub fn flash_borrow<'info>(ctx: Context<'_, '_, '_, 'info, FlashBorrow<'info>>, amount: u64) -> Result<()> {
+        process_flash_borrow(ctx, amount)
+    }
+
+    pub fn flash_repay<'info>(ctx: Context<'_, '_, '_, 'info, FlashRepay<'info>>) -> Result<()> {
+        process_flash_repay(ctx)
+    }
+
+    pub fn get_bank_rates(ctx: Context<GetBankRates>) -> Result<BankRates> {
+        process_get_bank_rates(ctx)
+    }
+
 
 }
 
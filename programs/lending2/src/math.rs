@@ -0,0 +1,227 @@
+use std::fmt;
+
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_MINT_DECIMALS;
+use crate::error::ErrorCode;
+
+pub use uint_types::U192;
+
+// Kept in its own module so the macro does not pick up anchor's `Result` alias
+#[allow(clippy::all)]
+mod uint_types {
+    use uint::construct_uint;
+
+    construct_uint! {
+        pub struct U192(3);
+    }
+}
+
+/// Number of decimal places kept by `Decimal`
+pub const SCALE: usize = 18;
+/// Raw value of `Decimal::one()`
+pub const WAD: u64 = 1_000_000_000_000_000_000;
+
+/// Unsigned fixed-point number with 18 decimals, used for prices, values,
+/// rates and share ratios. Every operation is checked and fails with
+/// `ErrorCode::MathOverflow`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Decimal(pub U192);
+
+impl Decimal {
+    pub fn zero() -> Self {
+        Self(U192::zero())
+    }
+
+    pub fn one() -> Self {
+        Self(U192::from(WAD))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    /// `numerator / denominator`, e.g. a bps value over its rate scale
+    pub fn from_ratio(numerator: u64, denominator: u64) -> Result<Self> {
+        Self::from(numerator).try_div(Self::from(denominator))
+    }
+
+    /// Converts a Pyth style `price * 10^exponent` into a decimal
+    pub fn from_price(price: i64, exponent: i32) -> Result<Self> {
+        require!(price > 0, ErrorCode::InvalidPrice);
+        Self::from_scaled(price as u64, exponent)
+    }
+
+    /// `value * 10^exponent`, e.g. a Pyth confidence interval
+    pub fn from_scaled(value: u64, exponent: i32) -> Result<Self> {
+        let value = Self::from(value);
+        let factor = Self::from(ten_pow(exponent.unsigned_abs())?);
+
+        if exponent >= 0 {
+            value.try_mul(factor)
+        } else {
+            value.try_div(factor)
+        }
+    }
+
+    /// Native `amount` of a mint with `decimals` expressed in whole tokens
+    pub fn from_token_amount(amount: u64, decimals: u8) -> Result<Self> {
+        Self::from(amount).try_div(Self::from(token_scale(decimals)?))
+    }
+
+    /// Whole tokens back to native units of a mint with `decimals`, rounded down
+    pub fn try_floor_token_amount(self, decimals: u8) -> Result<u64> {
+        self.try_mul(Self::from(token_scale(decimals)?))?.try_floor_u64()
+    }
+
+    pub fn try_add(self, rhs: Self) -> Result<Self> {
+        Ok(Self(self.0.checked_add(rhs.0).ok_or(ErrorCode::MathOverflow)?))
+    }
+
+    pub fn try_sub(self, rhs: Self) -> Result<Self> {
+        Ok(Self(self.0.checked_sub(rhs.0).ok_or(ErrorCode::MathOverflow)?))
+    }
+
+    pub fn try_mul(self, rhs: Self) -> Result<Self> {
+        Ok(Self(
+            self.0
+                .checked_mul(rhs.0)
+                .ok_or(ErrorCode::MathOverflow)?
+                .checked_div(U192::from(WAD))
+                .ok_or(ErrorCode::MathOverflow)?,
+        ))
+    }
+
+    pub fn try_div(self, rhs: Self) -> Result<Self> {
+        Ok(Self(
+            self.0
+                .checked_mul(U192::from(WAD))
+                .ok_or(ErrorCode::MathOverflow)?
+                .checked_div(rhs.0)
+                .ok_or(ErrorCode::MathOverflow)?,
+        ))
+    }
+
+    pub fn try_floor_u64(self) -> Result<u64> {
+        let value = self.0.checked_div(U192::from(WAD)).ok_or(ErrorCode::MathOverflow)?;
+        to_u64(value)
+    }
+}
+
+impl From<u64> for Decimal {
+    fn from(value: u64) -> Self {
+        // u64 * WAD always fits in 192 bits
+        Self(U192::from(value) * U192::from(WAD))
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut digits = self.0.to_string();
+        if digits.len() <= SCALE {
+            digits = format!("{}{}", "0".repeat(SCALE - digits.len() + 1), digits);
+        }
+        digits.insert(digits.len() - SCALE, '.');
+        f.write_str(&digits)
+    }
+}
+
+/// `a * b / c` rounded down, computed without intermediate overflow
+pub fn mul_div_floor(a: u64, b: u64, c: u64) -> Result<u64> {
+    let value = U192::from(a)
+        .checked_mul(U192::from(b))
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_div(U192::from(c))
+        .ok_or(ErrorCode::MathOverflow)?;
+    to_u64(value)
+}
+
+/// `a * b / c` rounded up, computed without intermediate overflow
+pub fn mul_div_ceil(a: u64, b: u64, c: u64) -> Result<u64> {
+    require!(c > 0, ErrorCode::MathOverflow);
+    let value = U192::from(a)
+        .checked_mul(U192::from(b))
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_add(U192::from(c - 1))
+        .ok_or(ErrorCode::MathOverflow)?
+        / U192::from(c);
+    to_u64(value)
+}
+
+/// `10^decimals`, rejecting mints we cannot value
+pub fn token_scale(decimals: u8) -> Result<u64> {
+    require!(decimals <= MAX_MINT_DECIMALS, ErrorCode::InvalidDecimals);
+    ten_pow(decimals as u32)
+}
+
+fn ten_pow(exponent: u32) -> Result<u64> {
+    10u64.checked_pow(exponent).ok_or(ErrorCode::MathOverflow.into())
+}
+
+fn to_u64(value: U192) -> Result<u64> {
+    if value > U192::from(u64::MAX) {
+        return Err(ErrorCode::MathOverflow.into());
+    }
+    Ok(value.as_u64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_overflow<T: fmt::Debug>(result: Result<T>) {
+        assert_eq!(result.unwrap_err(), ErrorCode::MathOverflow.into());
+    }
+
+    #[test]
+    fn mul_div_rounding() {
+        // 10 * 3 / 4 = 7.5
+        assert_eq!(mul_div_floor(10, 3, 4).unwrap(), 7);
+        assert_eq!(mul_div_ceil(10, 3, 4).unwrap(), 8);
+
+        // Exact results are not rounded
+        assert_eq!(mul_div_floor(10, 4, 4).unwrap(), 10);
+        assert_eq!(mul_div_ceil(10, 4, 4).unwrap(), 10);
+        assert_eq!(mul_div_ceil(0, 3, 4).unwrap(), 0);
+
+        // The intermediate product does not need to fit in a u64
+        assert_eq!(mul_div_floor(u64::MAX, u64::MAX, u64::MAX).unwrap(), u64::MAX);
+        assert_eq!(mul_div_ceil(u64::MAX, u64::MAX, u64::MAX).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn overflow_is_math_overflow() {
+        assert_overflow(mul_div_floor(u64::MAX, 2, 1));
+        assert_overflow(mul_div_ceil(u64::MAX, 2, 1));
+        assert_overflow(mul_div_floor(1, 1, 0));
+        assert_overflow(mul_div_ceil(1, 1, 0));
+
+        let max = Decimal::from(u64::MAX);
+        assert_overflow(max.try_mul(max));
+        assert_overflow(Decimal::zero().try_sub(Decimal::one()));
+        assert_overflow(Decimal::one().try_div(Decimal::zero()));
+        assert_overflow(max.try_add(Decimal::one()).unwrap().try_floor_u64());
+    }
+
+    #[test]
+    fn from_price_negative_exponents() {
+        assert_eq!(Decimal::from_price(12_345, -2).unwrap(), Decimal::from_ratio(12_345, 100).unwrap());
+        assert_eq!(Decimal::from_price(100, 2).unwrap(), Decimal::from(10_000));
+        assert_eq!(Decimal::from_price(1, -18).unwrap(), Decimal(U192::one()));
+        // Pyth SOL/USD at 8 decimals
+        assert_eq!(Decimal::from_price(15_012_345_678, -8).unwrap().to_string(), "150.123456780000000000");
+
+        assert_eq!(Decimal::from_price(0, -8).unwrap_err(), ErrorCode::InvalidPrice.into());
+        assert_eq!(Decimal::from_price(-1, -8).unwrap_err(), ErrorCode::InvalidPrice.into());
+        assert_overflow(Decimal::from_price(1, -20));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Decimal::zero().to_string(), "0.000000000000000000");
+        assert_eq!(Decimal::one().to_string(), "1.000000000000000000");
+        assert_eq!(Decimal::from_ratio(3, 2).unwrap().to_string(), "1.500000000000000000");
+        assert_eq!(Decimal(U192::one()).to_string(), "0.000000000000000001");
+        assert_eq!(Decimal::from(1_234).to_string(), "1234.000000000000000000");
+    }
+}
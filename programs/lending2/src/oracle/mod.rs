@@ -0,0 +1,114 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{CONFIDENCE_RATE_SCALE, ORACLE_DIVERGENCE_RATE_SCALE};
+use crate::error::ErrorCode;
+use crate::math::Decimal;
+use crate::state::{Bank, OracleSetup, OracleSource};
+
+pub mod pyth;
+pub mod stake_pool;
+pub mod switchboard;
+
+/// USD price of one whole token, with the bounds used to value positions
+#[derive(Clone, Copy)]
+pub struct OraclePrice {
+    pub price: Decimal,
+    /// Lower end of the confidence interval(s), used to value collateral
+    pub low: Decimal,
+    /// Upper end of the confidence interval(s), used to value debt
+    pub high: Decimal,
+}
+
+impl OraclePrice {
+    fn new(price: Decimal, confidence: Decimal) -> Result<Self> {
+        Ok(Self {
+            price,
+            low: price.try_sub(confidence).unwrap_or_default(),
+            high: price.try_add(confidence)?,
+        })
+    }
+
+    pub fn collateral_price(&self) -> Decimal {
+        self.low
+    }
+
+    pub fn debt_price(&self) -> Decimal {
+        self.high
+    }
+}
+
+/// Reads the bank's price from its primary `oracle`.
+///
+/// When the bank has a fallback oracle, `fallback` must be its account: the
+/// fallback price is used only if the primary is stale, and when both are
+/// readable they must agree within `max_oracle_divergence_bps`. Any error
+/// other than staleness, such as a wrong account, fails the instruction.
+pub fn get_oracle_price(
+    bank: &Bank,
+    oracle: &AccountInfo,
+    fallback: Option<&AccountInfo>,
+    clock: &Clock,
+) -> Result<OraclePrice> {
+    let primary = read_price(bank, &bank.primary_oracle(), oracle, clock);
+
+    let Some(fallback_source) = bank.fallback_oracle else {
+        return primary;
+    };
+    if let Err(error) = &primary {
+        if !is_stale(error) {
+            return primary;
+        }
+    }
+    let fallback = fallback.ok_or(ErrorCode::InvalidOracleAccount)?;
+    let secondary = read_price(bank, &fallback_source, fallback, clock);
+
+    match (primary, secondary) {
+        (Ok(primary), Ok(secondary)) => {
+            let divergence = if primary.price > secondary.price {
+                primary.price.try_sub(secondary.price)?
+            } else {
+                secondary.price.try_sub(primary.price)?
+            };
+            let max_divergence = primary
+                .price
+                .try_mul(Decimal::from_ratio(bank.max_oracle_divergence_bps, ORACLE_DIVERGENCE_RATE_SCALE)?)?;
+            require!(divergence <= max_divergence, ErrorCode::OracleDivergence);
+            Ok(primary)
+        }
+        // A wrong fallback account must not skip the divergence check either
+        (Ok(primary), Err(error)) if is_stale(&error) => Ok(primary),
+        (Ok(_), Err(error)) => Err(error),
+        (Err(_), Ok(secondary)) => {
+            msg!("Primary oracle stale, using fallback");
+            Ok(secondary)
+        }
+        (Err(error), Err(_)) => Err(error),
+    }
+}
+
+/// Staleness is the only oracle failure the other source may cover
+fn is_stale(error: &Error) -> bool {
+    *error == ErrorCode::StalePrice.into() || *error == ErrorCode::StaleLstRate.into()
+}
+
+/// Reads `oracle` with the adapter of `source.setup`
+fn read_price(bank: &Bank, source: &OracleSource, oracle: &AccountInfo, clock: &Clock) -> Result<OraclePrice> {
+    if source.oracle != Pubkey::default() {
+        require_keys_eq!(oracle.key(), source.oracle, ErrorCode::InvalidOracleAccount);
+    }
+
+    match source.setup {
+        OracleSetup::PythPull => pyth::get_price(bank, source, oracle, clock),
+        OracleSetup::SwitchboardPull => switchboard::get_price(bank, oracle, clock),
+        OracleSetup::StakePool { .. } => stake_pool::get_price(bank, source, oracle, clock),
+        OracleSetup::Fixed { price, exponent } => OraclePrice::new(Decimal::from_price(price, exponent)?, Decimal::zero()),
+    }
+}
+
+/// Rejects a price whose confidence interval is wider than `max_confidence_bps`
+fn checked_price(bank: &Bank, price: Decimal, confidence: Decimal) -> Result<OraclePrice> {
+    let max_confidence = price.try_mul(Decimal::from_ratio(bank.max_confidence_bps, CONFIDENCE_RATE_SCALE)?)?;
+    require!(confidence <= max_confidence, ErrorCode::PriceConfidenceTooWide);
+
+    OraclePrice::new(price, confidence)
+}
@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+use pyth_solana_receiver_sdk::error::GetPriceError;
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+
+use crate::error::ErrorCode;
+use crate::math::Decimal;
+use crate::state::{Bank, OracleSource, PriceMode};
+
+use super::{checked_price, OraclePrice};
+
+/// Reads a Pyth `PriceUpdateV2` for `source.price_feed_id` in the bank's
+/// `price_mode`, rejecting it when older than `max_price_age`
+pub fn get_price(bank: &Bank, source: &OracleSource, oracle: &AccountInfo, clock: &Clock) -> Result<OraclePrice> {
+    require_keys_eq!(*oracle.owner, PriceUpdateV2::owner(), ErrorCode::InvalidOracleAccount);
+    let price_update = PriceUpdateV2::try_deserialize(&mut &oracle.try_borrow_data()?[..])?;
+
+    // Checks the feed id, verification level and age of the update
+    let spot_data = price_update
+        .get_price_no_older_than(clock, bank.max_price_age, &source.price_feed_id)
+        .map_err(|error| match error {
+            GetPriceError::PriceTooOld => ErrorCode::StalePrice.into(),
+            error => Error::from(error),
+        })?;
+    let message = &price_update.price_message;
+
+    let spot = || pyth_price(bank, spot_data.price, spot_data.conf, spot_data.exponent);
+    let ema = || pyth_price(bank, message.ema_price, message.ema_conf, message.exponent);
+
+    match bank.price_mode {
+        PriceMode::Spot => spot(),
+        PriceMode::Ema => ema(),
+        // Values at the spot price, but collateral and debt at whichever of
+        // spot and EMA is worse for the user
+        PriceMode::Conservative => {
+            let spot = spot()?;
+            let ema = ema()?;
+            Ok(OraclePrice {
+                price: spot.price,
+                low: spot.low.min(ema.low),
+                high: spot.high.max(ema.high),
+            })
+        }
+    }
+}
+
+fn pyth_price(bank: &Bank, price: i64, confidence: u64, exponent: i32) -> Result<OraclePrice> {
+    checked_price(
+        bank,
+        Decimal::from_price(price, exponent)?,
+        Decimal::from_scaled(confidence, exponent)?,
+    )
+}
@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{LST_RATE_CHANGE_RATE_SCALE, MAX_LST_RATE_AGE_EPOCHS};
+use crate::error::ErrorCode;
+use crate::math::{Decimal, U192};
+use crate::state::{Bank, OracleSource};
+
+use super::{pyth, OraclePrice};
+
+/// `AccountType::StakePool` tag in the first byte of the account
+const STAKE_POOL_ACCOUNT_TYPE: u8 = 1;
+
+// Byte offsets in the borsh-encoded SPL `StakePool` account
+const POOL_MINT_OFFSET: usize = 162;
+const TOTAL_LAMPORTS_OFFSET: usize = 258;
+const POOL_TOKEN_SUPPLY_OFFSET: usize = 266;
+const LAST_UPDATE_EPOCH_OFFSET: usize = 274;
+const STAKE_POOL_MIN_LEN: usize = 282;
+
+/// LST price: the Pyth SOL/USD price times the bank's stored exchange rate
+pub fn get_price(bank: &Bank, source: &OracleSource, oracle: &AccountInfo, clock: &Clock) -> Result<OraclePrice> {
+    let sol_price = pyth::get_price(bank, source, oracle, clock)?;
+
+    require!(
+        bank.lst_exchange_rate > 0
+            && clock.epoch.saturating_sub(bank.lst_rate_updated_epoch) <= MAX_LST_RATE_AGE_EPOCHS,
+        ErrorCode::StaleLstRate
+    );
+    let rate = Decimal(U192::from(bank.lst_exchange_rate));
+
+    Ok(OraclePrice {
+        price: sol_price.price.try_mul(rate)?,
+        low: sol_price.low.try_mul(rate)?,
+        high: sol_price.high.try_mul(rate)?,
+    })
+}
+
+/// Reads SOL per pool token from an SPL stake pool (or a fork with the same
+/// layout) and stores it on the bank, bounded by `max_lst_rate_change_bps`
+pub fn refresh_exchange_rate(bank: &mut Bank, stake_pool: &AccountInfo, clock: &Clock) -> Result<()> {
+    let data = stake_pool.try_borrow_data()?;
+    require!(
+        data.len() >= STAKE_POOL_MIN_LEN && data[0] == STAKE_POOL_ACCOUNT_TYPE,
+        ErrorCode::InvalidStakePool
+    );
+
+    let pool_mint = Pubkey::new_from_array(read_bytes(&data, POOL_MINT_OFFSET));
+    require_keys_eq!(pool_mint, bank.mint_address, ErrorCode::InvalidStakePool);
+
+    // Totals are only accurate right after the pool's epoch update
+    let last_update_epoch = u64::from_le_bytes(read_bytes(&data, LAST_UPDATE_EPOCH_OFFSET));
+    require!(last_update_epoch == clock.epoch, ErrorCode::StakePoolNotUpdated);
+
+    let total_lamports = u64::from_le_bytes(read_bytes(&data, TOTAL_LAMPORTS_OFFSET));
+    let pool_token_supply = u64::from_le_bytes(read_bytes(&data, POOL_TOKEN_SUPPLY_OFFSET));
+    require!(total_lamports > 0 && pool_token_supply > 0, ErrorCode::InvalidStakePool);
+
+    // Pool tokens and SOL both have 9 decimals
+    let rate = Decimal::from_ratio(total_lamports, pool_token_supply)?;
+
+    if bank.lst_exchange_rate > 0 {
+        let previous_rate = Decimal(U192::from(bank.lst_exchange_rate));
+        let change = if rate > previous_rate {
+            rate.try_sub(previous_rate)?
+        } else {
+            previous_rate.try_sub(rate)?
+        };
+        // The bound is per epoch, so missed refreshes never lock the rate out
+        let epochs_elapsed = clock.epoch.saturating_sub(bank.lst_rate_updated_epoch).max(1);
+        let max_change = previous_rate.try_mul(Decimal::from_ratio(
+            bank.max_lst_rate_change_bps.saturating_mul(epochs_elapsed),
+            LST_RATE_CHANGE_RATE_SCALE,
+        )?)?;
+        require!(change <= max_change, ErrorCode::LstRateChangeTooLarge);
+    }
+
+    bank.lst_exchange_rate = rate.0.as_u128();
+    bank.lst_rate_updated_epoch = clock.epoch;
+
+    msg!("LST Exchange Rate: {}", rate);
+
+    Ok(())
+}
+
+fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> [u8; N] {
+    let mut bytes = [0u8; N];
+    bytes.copy_from_slice(&data[offset..offset + N]);
+    bytes
+}
@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+use anchor_lang::pubkey;
+
+use crate::error::ErrorCode;
+use crate::math::{Decimal, U192};
+use crate::state::Bank;
+
+use super::{checked_price, OraclePrice};
+
+/// Switchboard On-Demand program, owner of every `PullFeedAccountData`
+pub const SWITCHBOARD_ON_DEMAND_PROGRAM_ID: Pubkey = pubkey!("SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv");
+
+/// Anchor discriminator of `PullFeedAccountData`
+const PULL_FEED_DISCRIMINATOR: [u8; 8] = [196, 27, 108, 196, 10, 215, 219, 40];
+
+// Byte offsets in the zero-copy `PullFeedAccountData` account, discriminator included:
+// 32 oracle submissions (64 bytes each), then the feed settings, then `result`
+const LAST_UPDATE_TIMESTAMP_OFFSET: usize = 2216;
+const RESULT_VALUE_OFFSET: usize = 2264;
+const RESULT_STD_DEV_OFFSET: usize = 2280;
+const RESULT_NUM_SAMPLES_OFFSET: usize = 2360;
+const PULL_FEED_MIN_LEN: usize = 2392;
+
+/// Reads the current result of a Switchboard On-Demand pull feed. Values are
+/// signed 18-decimal fixed point, the standard deviation is used as confidence.
+pub fn get_price(bank: &Bank, oracle: &AccountInfo, clock: &Clock) -> Result<OraclePrice> {
+    require_keys_eq!(*oracle.owner, SWITCHBOARD_ON_DEMAND_PROGRAM_ID, ErrorCode::InvalidOracleAccount);
+
+    let data = oracle.try_borrow_data()?;
+    require!(
+        data.len() >= PULL_FEED_MIN_LEN && data[..8] == PULL_FEED_DISCRIMINATOR,
+        ErrorCode::InvalidOracleAccount
+    );
+
+    let last_update_timestamp = i64::from_le_bytes(read_bytes(&data, LAST_UPDATE_TIMESTAMP_OFFSET));
+    require!(
+        clock.unix_timestamp.saturating_sub(last_update_timestamp) <= bank.max_price_age as i64,
+        ErrorCode::StalePrice
+    );
+    require!(data[RESULT_NUM_SAMPLES_OFFSET] > 0, ErrorCode::InvalidPrice);
+
+    let value = i128::from_le_bytes(read_bytes(&data, RESULT_VALUE_OFFSET));
+    let std_dev = i128::from_le_bytes(read_bytes(&data, RESULT_STD_DEV_OFFSET));
+    require!(value > 0 && std_dev >= 0, ErrorCode::InvalidPrice);
+
+    // 18 decimals, the same scale as `Decimal`
+    checked_price(
+        bank,
+        Decimal(U192::from(value as u128)),
+        Decimal(U192::from(std_dev as u128)),
+    )
+}
+
+fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> [u8; N] {
+    let mut bytes = [0u8; N];
+    bytes.copy_from_slice(&data[offset..offset + N]);
+    bytes
+}
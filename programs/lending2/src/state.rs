@@ -1,19 +1,95 @@
 use anchor_lang::prelude::*;
+use pyth_solana_receiver_sdk::price_update::get_feed_id_from_hex;
+
+use crate::constants::{
+    INTEREST_RATE_SCALE,
+    CONFIDENCE_RATE_SCALE,
+    LIQUIDATION_BONUS_RATE_SCALE,
+    LIQUIDATION_CLOSE_FACTOR_RATE_SCALE,
+    LIQUIDATION_THRESHOLD_RATE_SCALE,
+    LOCKED_DEPOSIT_SHARES,
+    LST_RATE_CHANGE_RATE_SCALE,
+    RESERVE_FACTOR_RATE_SCALE,
+    MAX_BORROW_RATE,
+    MAX_FLASH_LOAN_FEE,
+    MAX_LIQUIDATION_BONUS,
+    MAX_POSITIONS,
+    MAX_TRANSFER_HOOK_PROGRAMS,
+    ORACLE_DIVERGENCE_RATE_SCALE,
+    PAUSE_ALL,
+    PAUSE_FROZEN,
+    SECONDS_PER_YEAR
+};
+use crate::error::ErrorCode;
+use crate::math::{mul_div_ceil, mul_div_floor, token_scale, Decimal};
+
+/// Isolated set of banks sharing one owner. Users, banks and treasuries
+/// are all derived from the market key.
+#[account]
+#[derive(InitSpace)]
+pub struct LendingMarket {
+    pub owner: Pubkey,
+    // Can pause operations but never unpause them
+    pub guardian: Pubkey,
+    pub fee_receiver: Pubkey,
+    pub pause_flags: u8,
+    // Token-2022 transfer hook programs accepted on bank mints
+    #[max_len(MAX_TRANSFER_HOOK_PROGRAMS)]
+    pub transfer_hook_allowlist: Vec<Pubkey>,
+}
+
+impl LendingMarket {
+    /// The owner may set any flags, the guardian may only add flags
+    pub fn check_pause_authority(&self, signer: &Pubkey, current_flags: u8, new_flags: u8) -> Result<()> {
+        require!(new_flags & !PAUSE_ALL == 0, ErrorCode::InvalidPauseFlags);
+
+        if *signer == self.owner {
+            return Ok(());
+        }
+        require!(
+            *signer == self.guardian && new_flags & current_flags == current_flags,
+            ErrorCode::Unauthorized
+        );
+        Ok(())
+    }
+}
 
 #[account]
 #[derive(InitSpace)]
 pub struct User {
 
     pub owner: Pubkey,  
-    pub total_deposit_value: u64,
-    pub total_borrow_value: u64,
+    pub lending_market: Pubkey,
+    // Mints of every UserTokenAccount with non-zero deposit or borrow shares,
+    // in the order their accounts must be passed for health checks
+    #[max_len(MAX_POSITIONS)]
+    pub positions: Vec<Pubkey>,
+}
+
+impl User {
+    pub fn add_position(&mut self, mint: Pubkey) -> Result<()> {
+        if self.positions.contains(&mint) {
+            return Ok(());
+        }
+        require!(self.positions.len() < MAX_POSITIONS, ErrorCode::TooManyPositions);
+        self.positions.push(mint);
+        Ok(())
+    }
+
+    /// Drops `mint` from the active positions once the token account is empty
+    pub fn refresh_position(&mut self, user_token_account: &UserTokenAccount) {
+        if user_token_account.deposit_shares == 0 && user_token_account.borrowed_shares == 0 {
+            self.positions.retain(|mint| mint != &user_token_account.mint);
+        }
+    }
 }
 
 #[account]
 #[derive(InitSpace)]
 pub struct Bank {
-    pub authority: Pubkey,
+    pub lending_market: Pubkey,
     pub mint_address: Pubkey,
+    pub mint_decimals: u8,
     pub total_deposits: u64,
     pub total_deposits_shares: u64,
     pub total_borrowed: u64,
@@ -23,16 +99,436 @@ pub struct Bank {
     pub liquidation_close_factor: u64,
     pub max_ltv: u64,
     pub last_updated: i64,
-    pub interest_rate: u64,
-    #[max_len(84)]
-    pub price_feed_id: String,
+    pub interest_rate_model: InterestRateModel,
+    pub price_feed_id: [u8; 32],
+    pub pause_flags: u8,
+    // Caps on the pool size, 0 = no cap. Token limits are in native units,
+    // USD limits in whole dollars at the current oracle price
+    pub deposit_limit: u64,
+    pub borrow_limit: u64,
+    pub deposit_limit_usd: u64,
+    pub borrow_limit_usd: u64,
+    // Share of borrow interest kept by the protocol, in bps
+    pub reserve_factor: u64,
+    // Protocol's part of the treasury balance, not owned by depositors
+    pub accumulated_protocol_fees: u64,
+    // Fee on flash loans, in bps
+    pub flash_loan_fee: u64,
+    // Amount out in the current transaction's flash loan, 0 when none
+    pub flash_loan_amount: u64,
+    // Oldest accepted oracle price, in seconds
+    pub max_price_age: u64,
+    // Widest accepted confidence interval, in bps of the price
+    pub max_confidence_bps: u64,
+    pub price_mode: PriceMode,
+    pub oracle_setup: OracleSetup,
+    // Required oracle account, or any account of the right type when default.
+    // Pyth updates are still matched by `price_feed_id`.
+    pub oracle: Pubkey,
+    // Secondary price source, used when the primary cannot be read
+    pub fallback_oracle: Option<OracleSource>,
+    // Largest accepted gap between primary and fallback prices, in bps
+    pub max_oracle_divergence_bps: u64,
+    // SOL per LST as an 18-decimal fixed point, 0 until the first refresh
+    pub lst_exchange_rate: u128,
+    pub lst_rate_updated_epoch: u64,
+    // Largest accepted exchange rate change per refresh, in bps
+    pub max_lst_rate_change_bps: u64,
+}
+
+/// A price source: adapter, account (default = any) and Pyth feed id
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct OracleSource {
+    pub setup: OracleSetup,
+    pub oracle: Pubkey,
+    pub price_feed_id: [u8; 32],
+}
+
+impl OracleSource {
+    pub fn validate(&self, price_mode: PriceMode) -> Result<()> {
+        match self.setup {
+            OracleSetup::PythPull => require!(self.price_feed_id != [0; 32], ErrorCode::InvalidPriceFeedId),
+            // Switchboard feeds have no EMA
+            OracleSetup::SwitchboardPull => require!(
+                self.oracle != Pubkey::default() && price_mode == PriceMode::Spot,
+                ErrorCode::InvalidOracleConfig
+            ),
+            OracleSetup::StakePool { stake_pool } => {
+                require!(self.price_feed_id != [0; 32], ErrorCode::InvalidPriceFeedId);
+                require!(stake_pool != Pubkey::default(), ErrorCode::InvalidOracleConfig);
+            }
+            OracleSetup::Fixed { price, exponent } => {
+                Decimal::from_price(price, exponent).map_err(|_| ErrorCode::InvalidOracleConfig)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Which adapter reads a bank's oracle account
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, InitSpace)]
+pub enum OracleSetup {
+    /// Pyth `PriceUpdateV2` for `price_feed_id`
+    #[default]
+    PythPull,
+    /// Switchboard On-Demand `PullFeedAccountData` at `oracle`
+    SwitchboardPull,
+    /// Admin-set `price * 10^exponent`, for pegged assets and local tests.
+    /// The oracle account passed to instructions is ignored.
+    Fixed { price: i64, exponent: i32 },
+    /// Liquid staking token: SOL/USD from Pyth `price_feed_id` times the
+    /// `Bank.lst_exchange_rate` refreshed from an SPL stake pool account
+    StakePool { stake_pool: Pubkey },
+}
+
+/// Which Pyth price a bank values positions with
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, InitSpace)]
+pub enum PriceMode {
+    #[default]
+    Spot,
+    /// Exponentially-weighted moving average, resistant to short wicks
+    Ema,
+    /// Lower of spot and EMA for collateral, higher of the two for debt
+    Conservative,
+}
+
+/// Kinked borrow rate curve, all values in `INTEREST_RATE_SCALE` bps.
+/// Below `optimal_utilization` the rate grows by `slope1`, above it by `slope2`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct InterestRateModel {
+    pub base_rate: u64,
+    pub optimal_utilization: u64,
+    pub slope1: u64,
+    pub slope2: u64,
+}
+
+impl InterestRateModel {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.optimal_utilization > 0 && self.optimal_utilization < INTEREST_RATE_SCALE,
+            ErrorCode::InvalidInterestRateModel
+        );
+
+        let max_rate = self
+            .base_rate
+            .checked_add(self.slope1)
+            .and_then(|rate| rate.checked_add(self.slope2))
+            .ok_or(ErrorCode::InvalidInterestRateModel)?;
+        require!(max_rate <= MAX_BORROW_RATE, ErrorCode::InvalidInterestRateModel);
+
+        Ok(())
+    }
+
+    /// Annual borrow rate for the given utilization, as a fraction.
+    pub fn borrow_rate(&self, utilization: Decimal) -> Result<Decimal> {
+        let utilization = utilization.min(Decimal::one());
+        let optimal_utilization = Decimal::from_ratio(self.optimal_utilization, INTEREST_RATE_SCALE)?;
+        let slope1 = Decimal::from_ratio(self.slope1, INTEREST_RATE_SCALE)?;
+        let slope2 = Decimal::from_ratio(self.slope2, INTEREST_RATE_SCALE)?;
+
+        let variable_rate = if utilization <= optimal_utilization {
+            if optimal_utilization.is_zero() {
+                Decimal::zero()
+            } else {
+                slope1.try_mul(utilization)?.try_div(optimal_utilization)?
+            }
+        } else {
+            let excess_utilization = utilization.try_sub(optimal_utilization)?;
+            let max_excess_utilization = Decimal::one().try_sub(optimal_utilization)?;
+
+            slope2
+                .try_mul(excess_utilization)?
+                .try_div(max_excess_utilization)?
+                .try_add(slope1)?
+        };
+
+        Decimal::from_ratio(self.base_rate, INTEREST_RATE_SCALE)?.try_add(variable_rate)
+    }
 }
 
+/// Current rates of a bank, returned by `get_bank_rates`. All values in bps.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct BankRates {
+    pub utilization: u64,
+    pub borrow_rate: u64,
+    pub supply_rate: u64,
+}
+
+impl Bank {
+    /// Parses a hex Pyth feed id (with or without `0x`) once, at configuration time.
+    /// An empty string is allowed for banks that do not use Pyth.
+    pub fn parse_price_feed_id(price_feed_id: &str) -> Result<[u8; 32]> {
+        if price_feed_id.is_empty() {
+            return Ok([0; 32]);
+        }
+        get_feed_id_from_hex(price_feed_id).map_err(|_| ErrorCode::InvalidPriceFeedId.into())
+    }
+
+    /// Fails when the bank or its whole market is frozen
+    pub fn check_not_frozen(&self, lending_market: &LendingMarket) -> Result<()> {
+        require!(
+            (self.pause_flags | lending_market.pause_flags) & PAUSE_FROZEN == 0,
+            ErrorCode::BankFrozen
+        );
+        Ok(())
+    }
+
+    /// Fails with `error` when `flag` is set on the bank or on its market
+    pub fn check_not_paused(&self, lending_market: &LendingMarket, flag: u8, error: ErrorCode) -> Result<()> {
+        self.check_not_frozen(lending_market)?;
+        if (self.pause_flags | lending_market.pause_flags) & flag != 0 {
+            return Err(error.into());
+        }
+        Ok(())
+    }
+
+    /// Checks `total_deposits`, after adding a deposit, against both deposit caps
+    pub fn check_deposit_limit(&self, price: Decimal) -> Result<()> {
+        require!(
+            is_within_limit(self.total_deposits, self.deposit_limit, self.deposit_limit_usd, self.mint_decimals, price)?,
+            ErrorCode::DepositLimitExceeded
+        );
+        Ok(())
+    }
+
+    /// Checks `total_borrowed`, after adding a borrow, against both borrow caps
+    pub fn check_borrow_limit(&self, price: Decimal) -> Result<()> {
+        require!(
+            is_within_limit(self.total_borrowed, self.borrow_limit, self.borrow_limit_usd, self.mint_decimals, price)?,
+            ErrorCode::BorrowLimitExceeded
+        );
+        Ok(())
+    }
+
+    pub fn primary_oracle(&self) -> OracleSource {
+        OracleSource {
+            setup: self.oracle_setup,
+            oracle: self.oracle,
+            price_feed_id: self.price_feed_id,
+        }
+    }
+
+    /// Checks that the risk parameters are consistent with each other
+    pub fn validate_config(&self) -> Result<()> {
+        require!(
+            self.liquidation_threshold > 0
+                && self.liquidation_threshold <= LIQUIDATION_THRESHOLD_RATE_SCALE,
+            ErrorCode::InvalidLiquidationThreshold
+        );
+        require!(
+            self.max_ltv < self.liquidation_threshold,
+            ErrorCode::InvalidMaxLtv
+        );
+
+        // Seizing collateral with the bonus must not take more than the
+        // threshold-weighted collateral backs, or liquidations create bad debt
+        require!(
+            self.liquidation_bonus <= MAX_LIQUIDATION_BONUS,
+            ErrorCode::InvalidLiquidationBonus
+        );
+        let max_seize = (self.liquidation_threshold as u128)
+            * (LIQUIDATION_BONUS_RATE_SCALE + self.liquidation_bonus) as u128;
+        require!(
+            max_seize <= (LIQUIDATION_THRESHOLD_RATE_SCALE * LIQUIDATION_BONUS_RATE_SCALE) as u128,
+            ErrorCode::InvalidLiquidationBonus
+        );
+
+        require!(
+            self.liquidation_close_factor > 0
+                && self.liquidation_close_factor <= LIQUIDATION_CLOSE_FACTOR_RATE_SCALE,
+            ErrorCode::InvalidLiquidationCloseFactor
+        );
+
+        require!(
+            self.reserve_factor <= RESERVE_FACTOR_RATE_SCALE,
+            ErrorCode::InvalidReserveFactor
+        );
+
+        require!(
+            self.flash_loan_fee <= MAX_FLASH_LOAN_FEE,
+            ErrorCode::InvalidFlashLoanFee
+        );
+
+        require!(
+            self.max_price_age > 0
+                && self.max_confidence_bps > 0
+                && self.max_confidence_bps <= CONFIDENCE_RATE_SCALE,
+            ErrorCode::InvalidOracleConfig
+        );
+
+        self.primary_oracle().validate(self.price_mode)?;
+        if let Some(fallback_oracle) = self.fallback_oracle {
+            fallback_oracle.validate(self.price_mode)?;
+            // `lst_exchange_rate` is only refreshed from the primary stake pool
+            require!(
+                !matches!(fallback_oracle.setup, OracleSetup::StakePool { .. }),
+                ErrorCode::InvalidOracleConfig
+            );
+            require!(
+                self.max_oracle_divergence_bps > 0
+                    && self.max_oracle_divergence_bps <= ORACLE_DIVERGENCE_RATE_SCALE,
+                ErrorCode::InvalidOracleConfig
+            );
+        }
+
+        require!(
+            self.max_lst_rate_change_bps <= LST_RATE_CHANGE_RATE_SCALE,
+            ErrorCode::InvalidOracleConfig
+        );
+
+        self.interest_rate_model.validate()?;
+
+        Ok(())
+    }
+
+    /// Share of deposits currently borrowed, as a fraction.
+    pub fn utilization(&self) -> Result<Decimal> {
+        if self.total_deposits == 0 {
+            return Ok(Decimal::zero());
+        }
+
+        Ok(Decimal::from_ratio(self.total_borrowed, self.total_deposits)?.min(Decimal::one()))
+    }
+
+    pub fn rates(&self) -> Result<BankRates> {
+        let utilization = self.utilization()?;
+        let borrow_rate = self.interest_rate_model.borrow_rate(utilization)?;
+
+        // Depositors earn the borrow rate on the borrowed share of the pool,
+        // minus what the reserve factor diverts to the protocol
+        let supply_rate = borrow_rate
+            .try_mul(utilization)?
+            .try_mul(Decimal::one().try_sub(Decimal::from_ratio(self.reserve_factor, RESERVE_FACTOR_RATE_SCALE)?)?)?;
+
+        let bps = Decimal::from(INTEREST_RATE_SCALE);
+        Ok(BankRates {
+            utilization: utilization.try_mul(bps)?.try_floor_u64()?,
+            borrow_rate: borrow_rate.try_mul(bps)?.try_floor_u64()?,
+            supply_rate: supply_rate.try_mul(bps)?.try_floor_u64()?,
+        })
+    }
+
+    // Share conversions always round in the protocol's favor: depositors get
+    // fewer shares / tokens, borrowers owe more shares / tokens.
+
+    /// Deposit shares minted for depositing `amount`
+    pub fn deposit_shares_for_amount(&self, amount: u64) -> Result<u64> {
+        if self.total_deposits_shares == 0 || self.total_deposits == 0 {
+            return Ok(amount);
+        }
+        mul_div_floor(amount, self.total_deposits_shares, self.total_deposits)
+    }
+
+    /// Deposit shares the first deposit locks for good, so the share price of a
+    /// near-empty pool cannot be inflated to round later depositors down.
+    /// Capped at the square root of one whole token, e.g. a single share at 0 decimals.
+    pub fn locked_deposit_shares(&self) -> Result<u64> {
+        Ok(LOCKED_DEPOSIT_SHARES.min(token_scale(self.mint_decimals / 2)?))
+    }
+
+    /// Deposit shares burned for withdrawing `amount`
+    pub fn deposit_shares_to_withdraw(&self, amount: u64) -> Result<u64> {
+        if self.total_deposits == 0 {
+            return Ok(amount);
+        }
+        mul_div_ceil(amount, self.total_deposits_shares, self.total_deposits)
+    }
+
+    /// Tokens redeemable for `shares` deposit shares
+    pub fn deposit_amount_for_shares(&self, shares: u64) -> Result<u64> {
+        if self.total_deposits_shares == 0 {
+            return Ok(0);
+        }
+        mul_div_floor(shares, self.total_deposits, self.total_deposits_shares)
+    }
+
+    /// Borrow shares minted for borrowing `amount`
+    pub fn borrow_shares_for_amount(&self, amount: u64) -> Result<u64> {
+        if self.total_borrowed_shares == 0 || self.total_borrowed == 0 {
+            return Ok(amount);
+        }
+        mul_div_ceil(amount, self.total_borrowed_shares, self.total_borrowed)
+    }
+
+    /// Borrow shares burned by repaying `amount`
+    pub fn borrow_shares_to_repay(&self, amount: u64) -> Result<u64> {
+        if self.total_borrowed == 0 {
+            return Ok(0);
+        }
+        mul_div_floor(amount, self.total_borrowed_shares, self.total_borrowed)
+    }
+
+    /// Tokens owed for `shares` borrow shares
+    pub fn borrow_amount_for_shares(&self, shares: u64) -> Result<u64> {
+        if self.total_borrowed_shares == 0 {
+            return Ok(0);
+        }
+        mul_div_ceil(shares, self.total_borrowed, self.total_borrowed_shares)
+    }
+
+    /// Accrues interest on `total_borrowed` from `last_updated` up to `now` and
+    /// credits it to depositors and the protocol.
+    pub fn accrue_interest(&mut self, now: i64) -> Result<()> {
+        let elapsed = now.saturating_sub(self.last_updated);
+        if elapsed <= 0 {
+            return Ok(());
+        }
+
+        let borrow_rate = self.interest_rate_model.borrow_rate(self.utilization()?)?;
+
+        let interest = Decimal::from(self.total_borrowed)
+            .try_mul(borrow_rate)?
+            .try_mul(Decimal::from_ratio(elapsed as u64, SECONDS_PER_YEAR)?)?
+            .try_floor_u64()?;
+
+        self.total_borrowed = self
+            .total_borrowed
+            .checked_add(interest)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.credit_earnings(interest)?;
+
+        // Interest that rounds down to zero keeps accumulating time until it is non-zero
+        if interest > 0 || self.total_borrowed == 0 {
+            self.last_updated = now;
+        }
+
+        Ok(())
+    }
+
+    /// Splits `amount` earned by the pool between depositors (`total_deposits`)
+    /// and the protocol (`accumulated_protocol_fees`) by the reserve factor
+    pub fn credit_earnings(&mut self, amount: u64) -> Result<()> {
+        let protocol_fee = mul_div_floor(amount, self.reserve_factor, RESERVE_FACTOR_RATE_SCALE)?;
+
+        self.total_deposits = self
+            .total_deposits
+            .checked_add(amount - protocol_fee)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.accumulated_protocol_fees = self
+            .accumulated_protocol_fees
+            .checked_add(protocol_fee)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        Ok(())
+    }
+}
+
+fn is_within_limit(total: u64, limit: u64, limit_usd: u64, decimals: u8, price: Decimal) -> Result<bool> {
+    if limit > 0 && total > limit {
+        return Ok(false);
+    }
+    if limit_usd > 0 {
+        let total_value = Decimal::from_token_amount(total, decimals)?.try_mul(price)?;
+        return Ok(total_value <= Decimal::from(limit_usd));
+    }
+    Ok(true)
+}
 
 #[account]
 #[derive(InitSpace)]
 pub struct UserTokenAccount {
     pub owner: Pubkey,          // The user's address
+    pub lending_market: Pubkey, // The market the account belongs to
     #[max_len(8)]
     pub name: String,           // The token name
     pub mint: Pubkey,           // The token's mint address (e.g., USDC or SOL)
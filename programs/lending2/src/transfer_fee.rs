@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        transfer_fee::TransferFeeConfig,
+        BaseStateWithExtensions,
+        StateWithExtensions
+    },
+    state::Mint,
+};
+
+use crate::error::ErrorCode;
+
+/// Amount to send so that `amount` arrives after the mint's Token-2022
+/// transfer fee. Mints without a transfer fee return `amount` unchanged.
+pub fn amount_with_transfer_fee(mint: &AccountInfo, amount: u64, clock: &Clock) -> Result<u64> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(amount);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+    let Ok(transfer_fee_config) = mint.get_extension::<TransferFeeConfig>() else {
+        return Ok(amount);
+    };
+
+    let amount_with_fee = transfer_fee_config
+        .get_epoch_fee(clock.epoch)
+        .calculate_pre_fee_amount(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok(amount_with_fee.max(amount))
+}
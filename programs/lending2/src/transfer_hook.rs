@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{self, onchain::invoke_transfer_checked};
+use anchor_spl::token_interface::{self, TransferChecked};
+
+/// `token_interface::transfer_checked` that also runs Token-2022 transfer hooks.
+/// The hook's validation account and extra accounts are looked up in the
+/// context's remaining accounts, mints without a hook ignore them.
+pub fn transfer_checked<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferChecked<'info>>,
+    amount: u64,
+    decimals: u8,
+) -> Result<()> {
+    if *ctx.accounts.mint.owner != spl_token_2022::ID {
+        return token_interface::transfer_checked(ctx, amount, decimals);
+    }
+
+    invoke_transfer_checked(
+        ctx.program.key,
+        ctx.accounts.from,
+        ctx.accounts.mint,
+        ctx.accounts.to,
+        ctx.accounts.authority,
+        &ctx.remaining_accounts,
+        amount,
+        decimals,
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
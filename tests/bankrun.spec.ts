@@ -122,20 +122,23 @@ describe('Lending Smart Contract Tests', async () => {
       2
     );
 
+    const lendingMarket = Keypair.generate();
+
 
     [solBankAccount] = PublicKey.findProgramAddressSync(
-        [mintSOL.toBuffer()],
+        [lendingMarket.publicKey.toBuffer(), mintSOL.toBuffer()],
         program.programId
     );
   
     [solBankTokenAccount] = PublicKey.findProgramAddressSync(
-      [Buffer.from('treasury'), mintSOL.toBuffer()],
+      [Buffer.from('treasury'), lendingMarket.publicKey.toBuffer(), mintSOL.toBuffer()],
       program.programId
     );
 
     const [userAccount] = PublicKey.findProgramAddressSync(
         [
             Buffer.from('user'),
+            lendingMarket.publicKey.toBuffer(),
             signer.publicKey.toBuffer(),
         ],
         program.programId
@@ -145,6 +148,7 @@ describe('Lending Smart Contract Tests', async () => {
     const [userTokenAccount] = PublicKey.findProgramAddressSync(
         [
             Buffer.from('user-token'),
+            lendingMarket.publicKey.toBuffer(),
             signer.publicKey.toBuffer(),
             mintUSDC.toBuffer(),
         ],
@@ -154,6 +158,7 @@ describe('Lending Smart Contract Tests', async () => {
       const [userTokenAccount2] = PublicKey.findProgramAddressSync(
         [
             Buffer.from('user-token'),
+            lendingMarket.publicKey.toBuffer(),
             signer.publicKey.toBuffer(),
             mintSOL.toBuffer(),
         ],
@@ -161,22 +166,36 @@ describe('Lending Smart Contract Tests', async () => {
       );
 
      [usdcBankAccount] = PublicKey.findProgramAddressSync(
-        [mintUSDC.toBuffer()],
+        [lendingMarket.publicKey.toBuffer(), mintUSDC.toBuffer()],
         program.programId
       );
 
     [usdcBankTokenAccount] = PublicKey.findProgramAddressSync(
-      [Buffer.from('treasury'), mintUSDC.toBuffer()],
+      [Buffer.from('treasury'), lendingMarket.publicKey.toBuffer(), mintUSDC.toBuffer()],
       program.programId
     );
     
   
+    it('Test Init Lending Market', async () => {
+      const initMarketTx = await program.methods
+        .initLendingMarket(signer.publicKey, signer.publicKey)
+        .accounts({
+          owner: signer.publicKey,
+          lendingMarket: lendingMarket.publicKey,
+        })
+        .signers([lendingMarket])
+        .rpc({ commitment: 'confirmed' });
+
+      console.log('Create Lending Market', initMarketTx);
+    });
+
     it('Test Init User1', async () => {
     console.log("mintUSDC: ", mintUSDC); 
       const initUserTx = await program.methods
-        .initUserTokenAccount("USDC", mintUSDC)
+        .initUserTokenAccount("USDC")
         .accounts({
           signer: signer.publicKey,
+          lendingMarket: lendingMarket.publicKey,
           mint: mintUSDC,
           userTokenAccount: userTokenAccount,
         })
@@ -187,9 +206,10 @@ describe('Lending Smart Contract Tests', async () => {
 
     it('Test Init User2', async () => {
           const initUserTx = await program.methods
-            .initUserTokenAccount("SOL", mintSOL)
+            .initUserTokenAccount("SOL")
             .accounts({
               signer: signer.publicKey,
+              lendingMarket: lendingMarket.publicKey,
               mint: mintSOL,
               userTokenAccount: userTokenAccount2,
             })
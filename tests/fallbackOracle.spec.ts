@@ -0,0 +1,189 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+// @ts-ignore
+import IDL from '../target/idl/lending2.json';
+import { Lending2 } from '../target/types/lending2';
+import { startAnchor } from 'solana-bankrun';
+import { BankrunProvider } from 'anchor-bankrun';
+import { BN, Program } from '@coral-xyz/anchor';
+import { Keypair, PublicKey } from '@solana/web3.js';
+import { createMint } from 'spl-token-bankrun';
+import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
+
+import { fixedPrice, marketFixture } from './marketFixture';
+import { buildSwitchboardPullFeed } from './oracleAccounts';
+
+// The USDC bank reads a Switchboard primary feed with a Switchboard fallback,
+// both built locally. SOL is a fixed-price bank used as collateral.
+describe('Fallback Oracle Tests', async () => {
+  const context = await startAnchor(
+    '',
+    [{ name: 'lending2', programId: new PublicKey(IDL.address) }],
+    []
+  );
+  const provider = new BankrunProvider(context);
+  const program = new Program<Lending2>(IDL as Lending2, provider);
+  const banksClient = context.banksClient;
+  const owner = provider.wallet.payer;
+
+  const lendingMarket = Keypair.generate();
+  const primaryFeed = Keypair.generate().publicKey;
+  const fallbackFeed = Keypair.generate().publicKey;
+
+  const mintUSDC = await createMint(
+    // @ts-ignore
+    banksClient,
+    owner,
+    owner.publicKey,
+    null,
+    6
+  );
+  const mintSOL = await createMint(
+    // @ts-ignore
+    banksClient,
+    owner,
+    owner.publicKey,
+    null,
+    9
+  );
+
+  const {
+    bankAccounts,
+    userAccountFor,
+    userTokenAccountFor,
+    initLendingMarket,
+    initBank,
+    updateBankConfig,
+    initUserTokenAccount,
+    fundUser,
+    deposit,
+    repay,
+  } = marketFixture(program, banksClient, owner, lendingMarket);
+
+  const usdc = bankAccounts(mintUSDC);
+  const sol = bankAccounts(mintSOL);
+
+  // Writes a feed priced at `price`, updated now or long enough ago to be stale
+  const setFeed = async (feed: PublicKey, price: number, stale = false) => {
+    const { unixTimestamp } = await banksClient.getClock();
+    context.setAccount(
+      feed,
+      buildSwitchboardPullFeed(price, 0.0001, stale ? unixTimestamp - BigInt(1_000) : unixTimestamp)
+    );
+  };
+
+  const depositUSDC = (amount: number, fallbackPriceUpdate: PublicKey | null = fallbackFeed) =>
+    program.methods
+      .deposit(new BN(amount))
+      .accountsPartial({
+        signer: owner.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint: mintUSDC,
+        userAccount: userAccountFor(owner.publicKey),
+        ...usdc,
+        userTokenAccount: userTokenAccountFor(owner.publicKey, mintUSDC),
+        priceUpdate: primaryFeed,
+        fallbackPriceUpdate,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc({ commitment: 'confirmed' });
+
+  // Positions in order: SOL, then USDC with its fallback feed last
+  const healthAccounts = () =>
+    [
+      sol.bank,
+      userTokenAccountFor(owner.publicKey, mintSOL),
+      sol.bank,
+      usdc.bank,
+      userTokenAccountFor(owner.publicKey, mintUSDC),
+      primaryFeed,
+      fallbackFeed,
+    ].map((pubkey) => ({ pubkey, isSigner: false, isWritable: false }));
+
+  const borrowUSDC = (amount: number) =>
+    program.methods
+      .borrow(new BN(amount))
+      .accountsPartial({
+        signer: owner.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mintBorrow: mintUSDC,
+        userAccount: userAccountFor(owner.publicKey),
+        bankBorrow: usdc.bank,
+        bankTokenAccountBorrow: usdc.bankTokenAccount,
+        userTokenAccountBorrow: userTokenAccountFor(owner.publicKey, mintUSDC),
+        priceUpdate: primaryFeed,
+        fallbackPriceUpdate: fallbackFeed,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(healthAccounts())
+      .rpc({ commitment: 'confirmed' });
+
+  it('Init Market, Banks and Positions', async () => {
+    await setFeed(primaryFeed, 1.0);
+    await setFeed(fallbackFeed, 1.01);
+
+    await initLendingMarket();
+    await initBank(mintSOL, fixedPrice(100));
+    await initBank(mintUSDC, fixedPrice(1));
+    await updateBankConfig(mintUSDC, {
+      oracleSetup: { switchboardPull: {} },
+      oracle: primaryFeed,
+      fallbackOracle: {
+        setup: { switchboardPull: {} },
+        oracle: fallbackFeed,
+        priceFeedId: '',
+      },
+      // 5%
+      maxOracleDivergenceBps: new BN(500),
+    });
+
+    await initUserTokenAccount(owner, mintSOL);
+    await initUserTokenAccount(owner, mintUSDC);
+    await fundUser(owner.publicKey, mintSOL, 10 * 10 ** 9);
+    await fundUser(owner.publicKey, mintUSDC, 2_000 * 10 ** 6);
+
+    await deposit(owner, mintSOL, 10 * 10 ** 9);
+    await depositUSDC(1_000 * 10 ** 6);
+  });
+
+  it('Use Fallback When the Primary Is Stale', async () => {
+    await setFeed(primaryFeed, 1.0, true);
+
+    await depositUSDC(10 ** 6);
+  });
+
+  it('Reject Stale Primary Without the Fallback Account', async () => {
+    await assert.rejects(depositUSDC(10 ** 6, null), /InvalidOracleAccount/);
+  });
+
+  it('Reject a Wrong Fallback Account', async () => {
+    await assert.rejects(depositUSDC(10 ** 6, primaryFeed), /InvalidOracleAccount/);
+  });
+
+  it('Reject Stale Primary and Fallback', async () => {
+    await setFeed(fallbackFeed, 1.01, true);
+
+    await assert.rejects(depositUSDC(2 * 10 ** 6), /StalePrice/);
+  });
+
+  it('Reject Diverging Oracles', async () => {
+    // 10% apart, above the 5% bound
+    await setFeed(primaryFeed, 1.0);
+    await setFeed(fallbackFeed, 1.1);
+
+    await assert.rejects(depositUSDC(3 * 10 ** 6), /OracleDivergence/);
+  });
+
+  it('Repay Without Any Oracle', async () => {
+    await setFeed(fallbackFeed, 1.01);
+    await borrowUSDC(100 * 10 ** 6);
+
+    // Neither feed can price USDC, repaying does not need them
+    await setFeed(primaryFeed, 1.0, true);
+    await setFeed(fallbackFeed, 1.01, true);
+    await repay(owner, mintUSDC, 50 * 10 ** 6);
+
+    const bankData = await program.account.bank.fetch(usdc.bank);
+    assert.equal(bankData.totalBorrowed.toString(), (50 * 10 ** 6).toString());
+  });
+});
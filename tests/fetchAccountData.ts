@@ -27,7 +27,7 @@ async function DisplayBankData( program, bankAccount) {
     console.log();
     // Format Bank Data
     console.log("Bank Data:");
-    console.log(`Authority: ${bankData.authority.toString()}`);
+    console.log(`Lending Market: ${bankData.lendingMarket.toString()}`);
     console.log(`- Mint Address: ${bankData.mintAddress.toBase58()}`);
     console.log(`Total Deposits: ${bankData.totalDeposits.toString()}`);
     console.log(`Total Deposit Shares: ${bankData.totalDepositsShares.toString()}`);
@@ -37,8 +37,13 @@ async function DisplayBankData( program, bankAccount) {
     console.log(`Liquidation Bonus: ${bankData.liquidationBonus.toString()}`);
     console.log(`Liquidation Close Factor: ${bankData.liquidationCloseFactor.toString()}`);
     console.log(`Max LTV: ${bankData.maxLtv.toString()}`);
+    console.log(`Reserve Factor: ${bankData.reserveFactor.toString()}`);
+    console.log(`Accumulated Protocol Fees: ${bankData.accumulatedProtocolFees.toString()}`);
     console.log(`Last Updated: ${bankData.lastUpdated.toString()}`);
-    console.log(`Interest Rate: ${(bankData.interestRate.toNumber() / 100).toFixed(2)}%`);
+    console.log(`Base Rate: ${(bankData.interestRateModel.baseRate.toNumber() / 100).toFixed(2)}%`);
+    console.log(`Optimal Utilization: ${(bankData.interestRateModel.optimalUtilization.toNumber() / 100).toFixed(2)}%`);
+    console.log(`Rate Slope 1: ${(bankData.interestRateModel.slope1.toNumber() / 100).toFixed(2)}%`);
+    console.log(`Rate Slope 2: ${(bankData.interestRateModel.slope2.toNumber() / 100).toFixed(2)}%`);
 }
 
 async function DisplayUser(program, userAccount ) {
@@ -49,8 +54,8 @@ async function DisplayUser(program, userAccount ) {
     console.log();
     // Format User Account Data
     console.log("User Data:");
-    console.log(`- Total Borrowed: $ ${userAccountData.totalBorrowValue.toString()}`);
-    console.log(`- Total Supplied: $ ${userAccountData.totalDepositValue.toString()}`);
+    console.log(`- Owner: ${userAccountData.owner.toBase58()}`);
+    console.log(`- Positions: ${userAccountData.positions.map((mint) => mint.toBase58()).join(', ')}`);
 
 }
 
@@ -65,7 +70,7 @@ async function fetchAndDisplayAccountData(program, connection, accounts) {
     console.log();
     // Format USDC Bank Data
     console.log("USDC Bank Data:");
-    console.log(`Authority: ${usdcBankData.authority.toString()}`);
+    console.log(`Lending Market: ${usdcBankData.lendingMarket.toString()}`);
     console.log(`Mint Address: ${usdcBankData.mintAddress.toString()}`);
     console.log(`Total Deposits: ${usdcBankData.totalDeposits.toString()}`);
     console.log(`Total Deposit Shares: ${usdcBankData.totalDepositsShares.toString()}`);
@@ -76,12 +81,15 @@ async function fetchAndDisplayAccountData(program, connection, accounts) {
     console.log(`Liquidation Close Factor: ${usdcBankData.liquidationCloseFactor.toString()}`);
     console.log(`Max LTV: ${usdcBankData.maxLtv.toString()}`);
     console.log(`Last Updated: ${usdcBankData.lastUpdated.toString()}`);
-    console.log(`Interest Rate: ${(usdcBankData.interestRate.toNumber() / 100).toFixed(2)}%`);
+    console.log(`Base Rate: ${(usdcBankData.interestRateModel.baseRate.toNumber() / 100).toFixed(2)}%`);
+    console.log(`Optimal Utilization: ${(usdcBankData.interestRateModel.optimalUtilization.toNumber() / 100).toFixed(2)}%`);
+    console.log(`Rate Slope 1: ${(usdcBankData.interestRateModel.slope1.toNumber() / 100).toFixed(2)}%`);
+    console.log(`Rate Slope 2: ${(usdcBankData.interestRateModel.slope2.toNumber() / 100).toFixed(2)}%`);
 
     console.log();
     // Format SOL Bank Data
     console.log("SOL Bank Data:");
-    console.log(`Authority: ${solBankData.authority.toString()}`);
+    console.log(`Lending Market: ${solBankData.lendingMarket.toString()}`);
     console.log(`- Mint Address: ${solBankData.mintAddress.toBase58()}`);
     console.log(`Total Deposits: ${solBankData.totalDeposits.toString()}`);
     console.log(`Total Deposit Shares: ${solBankData.totalDepositsShares.toString()}`);
@@ -92,7 +100,10 @@ async function fetchAndDisplayAccountData(program, connection, accounts) {
     console.log(`Liquidation Close Factor: ${solBankData.liquidationCloseFactor.toString()}`);
     console.log(`Max LTV: ${solBankData.maxLtv.toString()}`);
     console.log(`Last Updated: ${solBankData.lastUpdated.toString()}`);
-    console.log(`Interest Rate: ${(solBankData.interestRate.toNumber() / 100).toFixed(2)}%`);
+    console.log(`Base Rate: ${(solBankData.interestRateModel.baseRate.toNumber() / 100).toFixed(2)}%`);
+    console.log(`Optimal Utilization: ${(solBankData.interestRateModel.optimalUtilization.toNumber() / 100).toFixed(2)}%`);
+    console.log(`Rate Slope 1: ${(solBankData.interestRateModel.slope1.toNumber() / 100).toFixed(2)}%`);
+    console.log(`Rate Slope 2: ${(solBankData.interestRateModel.slope2.toNumber() / 100).toFixed(2)}%`);
 
     console.log();
     // Format User Account Data
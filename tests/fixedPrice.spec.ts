@@ -0,0 +1,233 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+// @ts-ignore
+import IDL from '../target/idl/lending2.json';
+import { Lending2 } from '../target/types/lending2';
+import { startAnchor } from 'solana-bankrun';
+import { BankrunProvider } from 'anchor-bankrun';
+import { BN, Program } from '@coral-xyz/anchor';
+import { Keypair, PublicKey } from '@solana/web3.js';
+import { createMint, mintTo } from 'spl-token-bankrun';
+import { TOKEN_PROGRAM_ID, getAssociatedTokenAddressSync } from '@solana/spl-token';
+
+import { fixedPrice, marketFixture } from './marketFixture';
+
+// Both banks use fixed prices, so nothing is fetched from the network
+describe('Fixed Price Oracle Tests', async () => {
+  const context = await startAnchor(
+    '',
+    [{ name: 'lending2', programId: new PublicKey(IDL.address) }],
+    []
+  );
+  const provider = new BankrunProvider(context);
+  const program = new Program<Lending2>(IDL as Lending2, provider);
+  const banksClient = context.banksClient;
+  const signer = provider.wallet.payer;
+
+  const lendingMarket = Keypair.generate();
+
+  const mintUSDC = await createMint(
+    // @ts-ignore
+    banksClient,
+    signer,
+    signer.publicKey,
+    null,
+    6
+  );
+  const mintSOL = await createMint(
+    // @ts-ignore
+    banksClient,
+    signer,
+    signer.publicKey,
+    null,
+    9
+  );
+
+  const {
+    bankAccounts,
+    userAccountFor,
+    userTokenAccountFor,
+    initLendingMarket,
+    initBank,
+    initUserTokenAccount,
+    fundUser,
+    deposit,
+    healthAccounts,
+  } = marketFixture(program, banksClient, signer, lendingMarket);
+
+  const withUserTokenAccount = (mint: PublicKey) => ({
+    ...bankAccounts(mint),
+    userTokenAccount: userTokenAccountFor(signer.publicKey, mint),
+  });
+  const usdc = withUserTokenAccount(mintUSDC);
+  const sol = withUserTokenAccount(mintSOL);
+  const userAccount = userAccountFor(signer.publicKey);
+
+  const fundAndDeposit = async (mint: PublicKey, amount: number) => {
+    await initUserTokenAccount(signer, mint);
+    await fundUser(signer.publicKey, mint, amount);
+    await deposit(signer, mint, amount);
+  };
+
+  const borrowSOL = (amount: number) =>
+    program.methods
+      .borrow(new BN(amount))
+      .accountsPartial({
+        signer: signer.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mintBorrow: mintSOL,
+        userAccount,
+        bankBorrow: sol.bank,
+        bankTokenAccountBorrow: sol.bankTokenAccount,
+        userTokenAccountBorrow: sol.userTokenAccount,
+        priceUpdate: sol.bank,
+        fallbackPriceUpdate: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      // Positions in order: SOL, then USDC
+      .remainingAccounts(healthAccounts(signer.publicKey, [mintSOL, mintUSDC]))
+      .rpc({ commitment: 'confirmed' });
+
+  it('Init Market and Fixed Price Banks', async () => {
+    await initLendingMarket();
+
+    await initBank(mintUSDC, fixedPrice(1));
+    await initBank(mintSOL, fixedPrice(150));
+  });
+
+  it('Deposit SOL and USDC', async () => {
+    await fundAndDeposit(mintSOL, 100 * 10 ** 9);
+    await fundAndDeposit(mintUSDC, 1_000 * 10 ** 6);
+
+    const userData = await program.account.user.fetch(userAccount);
+    assert.equal(userData.positions.length, 2);
+  });
+
+  it('Borrow SOL Within Max LTV', async () => {
+    await borrowSOL(2 * 10 ** 9);
+
+    const bankData = await program.account.bank.fetch(sol.bank);
+    assert.equal(bankData.totalBorrowed.toString(), (2 * 10 ** 9).toString());
+  });
+
+  it('Reject Zero Borrow', async () => {
+    // No shares would be minted, leaving a position that can never be closed
+    await assert.rejects(borrowSOL(0), /ZeroShares/);
+  });
+
+  it('Reject Borrow Above Max LTV', async () => {
+    // Borrow power: (100 SOL * $150 + $1000) * 75% = $12,000, i.e. 80 SOL
+    await assert.rejects(borrowSOL(90 * 10 ** 9), /OverBorrowableAmount/);
+  });
+
+  it('Repay All SOL Debt', async () => {
+    const userAta = getAssociatedTokenAddressSync(mintSOL, signer.publicKey);
+    // Cover any debt rounded up above the borrowed amount
+    // @ts-ignore
+    await mintTo(banksClient, signer, mintSOL, userAta, signer, 10 ** 9);
+
+    await program.methods
+      .repayAll()
+      .accountsPartial({
+        signer: signer.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint: mintSOL,
+        bank: sol.bank,
+        bankTokenAccount: sol.bankTokenAccount,
+        userAccount,
+        userTokenAccount: sol.userTokenAccount,
+        userAssociatedTokenAccount: userAta,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc({ commitment: 'confirmed' });
+
+    const userTokenData = await program.account.userTokenAccount.fetch(sol.userTokenAccount);
+    assert.equal(userTokenData.borrowedShares.toString(), '0');
+  });
+
+  it('Withdraw All USDC', async () => {
+    await program.methods
+      .withdrawAll()
+      .accountsPartial({
+        signer: signer.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint: mintUSDC,
+        userAccount,
+        ...usdc,
+        priceUpdate: usdc.bank,
+        fallbackPriceUpdate: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(healthAccounts(signer.publicKey, [mintSOL, mintUSDC]))
+      .rpc({ commitment: 'confirmed' });
+
+    const userTokenData = await program.account.userTokenAccount.fetch(usdc.userTokenAccount);
+    assert.equal(userTokenData.depositShares.toString(), '0');
+
+    const bankData = await program.account.bank.fetch(usdc.bank);
+    // Only the shares locked by the first deposit are left
+    assert.equal(bankData.totalDepositsShares.toString(), '1000');
+  });
+
+  it('Lock Fewer Shares on a Zero-Decimal Mint', async () => {
+    const mintWhole = await createMint(
+      // @ts-ignore
+      banksClient,
+      signer,
+      signer.publicKey,
+      null,
+      0
+    );
+    await initBank(mintWhole, fixedPrice(1));
+    await fundAndDeposit(mintWhole, 5);
+
+    // A single share is locked instead of 1000 whole tokens
+    const bankData = await program.account.bank.fetch(bankAccounts(mintWhole).bank);
+    assert.equal(bankData.totalDepositsShares.toString(), '5');
+    const userTokenData = await program.account.userTokenAccount.fetch(
+      userTokenAccountFor(signer.publicKey, mintWhole)
+    );
+    assert.equal(userTokenData.depositShares.toString(), '4');
+  });
+
+  it('Close Empty USDC Token Account', async () => {
+    await program.methods
+      .closeUserTokenAccount()
+      .accountsPartial({
+        signer: signer.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint: mintUSDC,
+        userTokenAccount: usdc.userTokenAccount,
+      })
+      .rpc({ commitment: 'confirmed' });
+
+    assert.equal(await banksClient.getAccount(usdc.userTokenAccount), null);
+  });
+
+  it('Reject Closing Accounts With Open Positions', async () => {
+    await assert.rejects(
+      program.methods
+        .closeUserTokenAccount()
+        .accountsPartial({
+          signer: signer.publicKey,
+          lendingMarket: lendingMarket.publicKey,
+          mint: mintSOL,
+          userTokenAccount: sol.userTokenAccount,
+        })
+        .rpc({ commitment: 'confirmed' }),
+      /UserTokenAccountNotEmpty/
+    );
+
+    await assert.rejects(
+      program.methods
+        .closeUser()
+        .accountsPartial({
+          signer: signer.publicKey,
+          lendingMarket: lendingMarket.publicKey,
+          userAccount,
+        })
+        .rpc({ commitment: 'confirmed' }),
+      /UserHasOpenPositions/
+    );
+  });
+});
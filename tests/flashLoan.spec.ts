@@ -0,0 +1,168 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { createHash } from 'node:crypto';
+// @ts-ignore
+import IDL from '../target/idl/lending2.json';
+import { Lending2 } from '../target/types/lending2';
+import { startAnchor } from 'solana-bankrun';
+import { BankrunProvider } from 'anchor-bankrun';
+import { BN, Program } from '@coral-xyz/anchor';
+import { Keypair, PublicKey, Transaction, TransactionInstruction } from '@solana/web3.js';
+import { createMint } from 'spl-token-bankrun';
+import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
+
+import { marketFixture } from './marketFixture';
+
+// Test helper program that forwards an instruction through CPI
+const CPI_CALLER_PROGRAM_ID = new PublicKey('AdayCxezRkZSCFCaZ55YPMokYpPZY5NFgoE2XeymHqga');
+
+const errorCode = (name: string) =>
+  IDL.errors.find((error: { name: string }) => error.name.toLowerCase() === name.toLowerCase())!.code;
+
+// Matches both translated Anchor errors and raw custom program errors
+const rejectsWith = (promise: Promise<unknown>, name: string) =>
+  assert.rejects(promise, (error: any) => {
+    const text = `${error} ${(error?.logs ?? []).join(' ')}`;
+    return text.includes(name) || text.includes(`0x${errorCode(name).toString(16)}`);
+  });
+
+describe('Flash Loan Tests', async () => {
+  const context = await startAnchor(
+    '',
+    [
+      { name: 'lending2', programId: new PublicKey(IDL.address) },
+      { name: 'cpi_caller', programId: CPI_CALLER_PROGRAM_ID },
+    ],
+    []
+  );
+  const provider = new BankrunProvider(context);
+  const program = new Program<Lending2>(IDL as Lending2, provider);
+  const banksClient = context.banksClient;
+  const signer = provider.wallet.payer;
+
+  const lendingMarket = Keypair.generate();
+
+  const mintUSDC = await createMint(
+    // @ts-ignore
+    banksClient,
+    signer,
+    signer.publicKey,
+    null,
+    6
+  );
+  const mintSOL = await createMint(
+    // @ts-ignore
+    banksClient,
+    signer,
+    signer.publicKey,
+    null,
+    9
+  );
+
+  const { bankAccounts, initLendingMarket, initBank, updateBankConfig, initUserTokenAccount, fundUser, deposit } =
+    marketFixture(program, banksClient, signer, lendingMarket);
+
+  const usdc = bankAccounts(mintUSDC);
+  const userAtas = new Map<string, PublicKey>();
+
+  const fundAndDeposit = async (mint: PublicKey, amount: number) => {
+    await initUserTokenAccount(signer, mint);
+    // Extra tokens pay the flash loan fees
+    userAtas.set(mint.toBase58(), await fundUser(signer.publicKey, mint, 2 * amount));
+    await deposit(signer, mint, amount);
+  };
+
+  const flashBorrow = (mint: PublicKey, amount: number) =>
+    program.methods.flashBorrow(new BN(amount)).accountsPartial({
+      signer: signer.publicKey,
+      lendingMarket: lendingMarket.publicKey,
+      mint,
+      bank: bankAccounts(mint).bank,
+      bankTokenAccount: bankAccounts(mint).bankTokenAccount,
+      destinationTokenAccount: userAtas.get(mint.toBase58()),
+      tokenProgram: TOKEN_PROGRAM_ID,
+    });
+
+  const flashRepay = (mint: PublicKey) =>
+    program.methods
+      .flashRepay()
+      .accountsPartial({
+        signer: signer.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint,
+        bank: bankAccounts(mint).bank,
+        bankTokenAccount: bankAccounts(mint).bankTokenAccount,
+        sourceTokenAccount: userAtas.get(mint.toBase58()),
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .instruction();
+
+  it('Init Market, Banks and Liquidity', async () => {
+    await initLendingMarket();
+
+    await initBank(mintUSDC);
+    await initBank(mintSOL);
+
+    await fundAndDeposit(mintUSDC, 1_000 * 10 ** 6);
+    await fundAndDeposit(mintSOL, 10 * 10 ** 9);
+
+    await updateBankConfig(mintUSDC, { flashLoanFee: new BN(30) });
+  });
+
+  it('Flash Borrow and Repay With Fee', async () => {
+    await flashBorrow(mintUSDC, 100 * 10 ** 6)
+      .postInstructions([await flashRepay(mintUSDC)])
+      .rpc({ commitment: 'confirmed' });
+
+    const bankData = await program.account.bank.fetch(usdc.bank);
+    assert.equal(bankData.flashLoanAmount.toString(), '0');
+    // 0.3% of 100 USDC goes to depositors
+    assert.equal(bankData.totalDeposits.toString(), (1_000_300_000).toString());
+  });
+
+  it('Reject Flash Borrow Without Repay', async () => {
+    await rejectsWith(
+      flashBorrow(mintUSDC, 100 * 10 ** 6).rpc({ commitment: 'confirmed' }),
+      'MissingFlashRepay'
+    );
+  });
+
+  it('Reject Flash Repay on Another Bank', async () => {
+    await rejectsWith(
+      flashBorrow(mintUSDC, 100 * 10 ** 6)
+        .postInstructions([await flashRepay(mintSOL)])
+        .rpc({ commitment: 'confirmed' }),
+      'MissingFlashRepay'
+    );
+  });
+
+  it('Reject Second Flash Borrow Before Repay', async () => {
+    const secondBorrow = await flashBorrow(mintUSDC, 10 ** 6).instruction();
+    await rejectsWith(
+      flashBorrow(mintUSDC, 100 * 10 ** 6)
+        .postInstructions([secondBorrow, await flashRepay(mintUSDC)])
+        .rpc({ commitment: 'confirmed' }),
+      'FlashLoanActive'
+    );
+  });
+
+  it('Reject Flash Borrow Through CPI', async () => {
+    const borrow = await flashBorrow(mintUSDC, 100 * 10 ** 6).instruction();
+
+    // `forward(data: Vec<u8>)` with the borrow's accounts as remaining accounts
+    const discriminator = createHash('sha256').update('global:forward').digest().subarray(0, 8);
+    const length = Buffer.alloc(4);
+    length.writeUInt32LE(borrow.data.length);
+    const forward = new TransactionInstruction({
+      programId: CPI_CALLER_PROGRAM_ID,
+      keys: [
+        { pubkey: program.programId, isSigner: false, isWritable: false },
+        ...borrow.keys,
+      ],
+      data: Buffer.concat([discriminator, length, borrow.data]),
+    });
+
+    const transaction = new Transaction().add(forward, await flashRepay(mintUSDC));
+    await rejectsWith(provider.sendAndConfirm(transaction), 'FlashLoanCpiNotAllowed');
+  });
+});
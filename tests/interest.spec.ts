@@ -0,0 +1,178 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+// @ts-ignore
+import IDL from '../target/idl/lending2.json';
+import { Lending2 } from '../target/types/lending2';
+import { Clock, startAnchor } from 'solana-bankrun';
+import { BankrunProvider } from 'anchor-bankrun';
+import { BN, Program } from '@coral-xyz/anchor';
+import { Keypair, PublicKey } from '@solana/web3.js';
+import { createMint, getAccount } from 'spl-token-bankrun';
+import { TOKEN_PROGRAM_ID, getAssociatedTokenAddressSync } from '@solana/spl-token';
+
+import { fixedPrice, marketFixture } from './marketFixture';
+
+const SECONDS_PER_YEAR = BigInt(31_536_000);
+
+// Interest accrues on the default curve: 5% APR at the 80% optimal
+// utilization, 80% APR at full utilization
+describe('Interest Tests', async () => {
+  const context = await startAnchor(
+    '',
+    [{ name: 'lending2', programId: new PublicKey(IDL.address) }],
+    []
+  );
+  const provider = new BankrunProvider(context);
+  const program = new Program<Lending2>(IDL as Lending2, provider);
+  const banksClient = context.banksClient;
+  const owner = provider.wallet.payer;
+
+  const lendingMarket = Keypair.generate();
+
+  const mintUSDC = await createMint(
+    // @ts-ignore
+    banksClient,
+    owner,
+    owner.publicKey,
+    null,
+    6
+  );
+  const mintSOL = await createMint(
+    // @ts-ignore
+    banksClient,
+    owner,
+    owner.publicKey,
+    null,
+    9
+  );
+
+  const {
+    bankAccounts,
+    initLendingMarket,
+    initBank,
+    updateBankConfig,
+    initUserTokenAccount,
+    fundUser,
+    deposit,
+    borrow,
+  } = marketFixture(program, banksClient, owner, lendingMarket);
+
+  const usdc = bankAccounts(mintUSDC);
+
+  const warp = async (seconds: bigint) => {
+    const clock = await banksClient.getClock();
+    context.setClock(
+      new Clock(
+        clock.slot,
+        clock.epochStartTimestamp,
+        clock.epoch,
+        clock.leaderScheduleEpoch,
+        clock.unixTimestamp + seconds
+      )
+    );
+  };
+
+  // Positions in order: SOL, then USDC
+  const borrowUSDC = (amount: number) => borrow(owner, mintUSDC, amount, [mintSOL, mintUSDC]);
+
+  it('Init Market, Banks and Positions', async () => {
+    await initLendingMarket();
+
+    await initBank(mintUSDC, fixedPrice(1));
+    await initBank(mintSOL, fixedPrice(100));
+
+    await initUserTokenAccount(owner, mintSOL);
+    await initUserTokenAccount(owner, mintUSDC);
+    await fundUser(owner.publicKey, mintSOL, 10 * 10 ** 9);
+    await fundUser(owner.publicKey, mintUSDC, 2_000 * 10 ** 6);
+
+    await deposit(owner, mintSOL, 10 * 10 ** 9);
+    await deposit(owner, mintUSDC, 1_000 * 10 ** 6);
+    await borrowUSDC(400 * 10 ** 6);
+  });
+
+  it('Accrue a Year of Interest on the Next Touch', async () => {
+    await warp(SECONDS_PER_YEAR);
+
+    // Nothing accrues until an instruction touches the bank
+    let bankData = await program.account.bank.fetch(usdc.bank);
+    assert.equal(bankData.totalBorrowed.toString(), (400 * 10 ** 6).toString());
+
+    await deposit(owner, mintUSDC, 10 ** 6);
+
+    // 40% utilization: 5% * 40% / 80% = 2.5% APR on 400 USDC
+    bankData = await program.account.bank.fetch(usdc.bank);
+    assert.equal(bankData.totalBorrowed.toString(), (410 * 10 ** 6).toString());
+    // Without a reserve factor depositors earn all of it
+    assert.equal(bankData.totalDeposits.toString(), (1_011 * 10 ** 6).toString());
+  });
+
+  const bankRates = () => program.methods.getBankRates().accounts({ bank: usdc.bank }).view();
+
+  it('Report Rates Below the Kink', async () => {
+    // 410 of 1011 USDC borrowed
+    const rates = await bankRates();
+    assert.equal(rates.utilization.toString(), '4055');
+    // 5% * 40.55% / 80%
+    assert.equal(rates.borrowRate.toString(), '253');
+    // Borrow rate times utilization
+    assert.equal(rates.supplyRate.toString(), '102');
+  });
+
+  it('Report Rates Above the Kink', async () => {
+    // 909.9 of 1011 USDC borrowed
+    await borrowUSDC(499_900_000);
+
+    const rates = await bankRates();
+    assert.equal(rates.utilization.toString(), '9000');
+    // 5% + 75% * (90% - 80%) / (100% - 80%)
+    assert.equal(rates.borrowRate.toString(), '4250');
+    assert.equal(rates.supplyRate.toString(), '3825');
+  });
+
+  // The owner is also the market's fee receiver
+  const feeReceiverAta = getAssociatedTokenAddressSync(mintUSDC, owner.publicKey);
+
+  const collectFees = () =>
+    program.methods
+      .collectFees()
+      .accountsPartial({
+        lendingMarket: lendingMarket.publicKey,
+        mint: mintUSDC,
+        ...usdc,
+        feeReceiverTokenAccount: feeReceiverAta,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc({ commitment: 'confirmed' });
+
+  const tokenBalance = async (address: PublicKey) =>
+    (
+      await getAccount(
+        // @ts-ignore
+        banksClient,
+        address
+      )
+    ).amount;
+
+  it('Collect the Reserve Factor Share of Interest', async () => {
+    // 10%
+    await updateBankConfig(mintUSDC, { reserveFactor: new BN(1_000) });
+    await warp(SECONDS_PER_YEAR);
+
+    const balanceBefore = await tokenBalance(feeReceiverAta);
+    await collectFees();
+
+    // 42.5% APR on 909.9 USDC is 386.7075 USDC, 10% of it goes to the protocol
+    const collected = (await tokenBalance(feeReceiverAta)) - balanceBefore;
+    assert.equal(collected.toString(), '38670750');
+
+    const bankData = await program.account.bank.fetch(usdc.bank);
+    assert.equal(bankData.accumulatedProtocolFees.toString(), '0');
+    assert.equal(bankData.totalDeposits.toString(), (1_011_000_000 + 348_036_750).toString());
+
+    // A new slot, so the same transaction is not deduplicated
+    const { slot } = await banksClient.getClock();
+    context.warpToSlot(slot + BigInt(1));
+    await assert.rejects(collectFees(), /NoFeesToCollect/);
+  });
+});
@@ -0,0 +1,106 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+// @ts-ignore
+import IDL from '../target/idl/lending2.json';
+import { Lending2 } from '../target/types/lending2';
+import { startAnchor } from 'solana-bankrun';
+import { BankrunProvider } from 'anchor-bankrun';
+import { BN, Program } from '@coral-xyz/anchor';
+import { Keypair, PublicKey } from '@solana/web3.js';
+import { createMint } from 'spl-token-bankrun';
+
+import { fixedPrice, marketFixture } from './marketFixture';
+
+// USDC is capped in tokens and SOL in USD, limits of 0 mean uncapped
+describe('Deposit and Borrow Limit Tests', async () => {
+  const context = await startAnchor(
+    '',
+    [{ name: 'lending2', programId: new PublicKey(IDL.address) }],
+    []
+  );
+  const provider = new BankrunProvider(context);
+  const program = new Program<Lending2>(IDL as Lending2, provider);
+  const banksClient = context.banksClient;
+  const owner = provider.wallet.payer;
+
+  const lendingMarket = Keypair.generate();
+
+  const mintUSDC = await createMint(
+    // @ts-ignore
+    banksClient,
+    owner,
+    owner.publicKey,
+    null,
+    6
+  );
+  const mintSOL = await createMint(
+    // @ts-ignore
+    banksClient,
+    owner,
+    owner.publicKey,
+    null,
+    9
+  );
+
+  const {
+    initLendingMarket,
+    initBank,
+    updateBankConfig,
+    initUserTokenAccount,
+    fundUser,
+    deposit,
+    borrow,
+  } = marketFixture(program, banksClient, owner, lendingMarket);
+
+  // Positions in order: USDC, then SOL
+  const positions = [mintUSDC, mintSOL];
+
+  it('Init Market and Banks', async () => {
+    await initLendingMarket();
+
+    await initBank(mintUSDC, fixedPrice(1));
+    await initBank(mintSOL, fixedPrice(100));
+
+    await initUserTokenAccount(owner, mintUSDC);
+    await initUserTokenAccount(owner, mintSOL);
+    await fundUser(owner.publicKey, mintUSDC, 2_000 * 10 ** 6);
+    await fundUser(owner.publicKey, mintSOL, 20 * 10 ** 9);
+  });
+
+  it('Reject Deposits Above the Token Limit', async () => {
+    await updateBankConfig(mintUSDC, { depositLimit: new BN(1_000 * 10 ** 6) });
+
+    // Reaching the limit exactly is allowed
+    await deposit(owner, mintUSDC, 1_000 * 10 ** 6);
+    await assert.rejects(deposit(owner, mintUSDC, 10 ** 6), /DepositLimitExceeded/);
+  });
+
+  it('Reject Deposits Above the USD Limit', async () => {
+    // 5 SOL at $100
+    await updateBankConfig(mintSOL, { depositLimitUsd: new BN(500) });
+
+    await deposit(owner, mintSOL, 5 * 10 ** 9);
+    await assert.rejects(deposit(owner, mintSOL, 10 ** 9), /DepositLimitExceeded/);
+  });
+
+  it('Reject Borrows Above the Token Limit', async () => {
+    await updateBankConfig(mintUSDC, { borrowLimit: new BN(100 * 10 ** 6) });
+
+    await borrow(owner, mintUSDC, 100 * 10 ** 6, positions);
+    await assert.rejects(borrow(owner, mintUSDC, 10 ** 6, positions), /BorrowLimitExceeded/);
+  });
+
+  it('Reject Borrows Above the USD Limit', async () => {
+    // 2 SOL at $100
+    await updateBankConfig(mintSOL, { borrowLimitUsd: new BN(200) });
+
+    await borrow(owner, mintSOL, 2 * 10 ** 9, positions);
+    await assert.rejects(borrow(owner, mintSOL, 10 ** 8, positions), /BorrowLimitExceeded/);
+  });
+
+  it('Removing a Limit Reopens the Bank', async () => {
+    await updateBankConfig(mintUSDC, { depositLimit: new BN(0) });
+
+    await deposit(owner, mintUSDC, 2 * 10 ** 6);
+  });
+});
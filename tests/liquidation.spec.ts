@@ -0,0 +1,272 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+// @ts-ignore
+import IDL from '../target/idl/lending2.json';
+import { Lending2 } from '../target/types/lending2';
+import { startAnchor } from 'solana-bankrun';
+import { BankrunProvider } from 'anchor-bankrun';
+import { BN, Program } from '@coral-xyz/anchor';
+import { Keypair, LAMPORTS_PER_SOL, PublicKey, SystemProgram, Transaction } from '@solana/web3.js';
+import { createMint, getAccount } from 'spl-token-bankrun';
+import { TOKEN_PROGRAM_ID, getAssociatedTokenAddressSync } from '@solana/spl-token';
+
+import { fixedPrice, marketFixture } from './marketFixture';
+
+// A fresh market where the borrower posts 10 SOL and borrows 700 USDC out of
+// the liquidator's 5000 USDC deposit, prices are moved by updating the banks'
+// fixed oracle prices
+const liquidationMarket = async () => {
+  const context = await startAnchor(
+    '',
+    [{ name: 'lending2', programId: new PublicKey(IDL.address) }],
+    []
+  );
+  const provider = new BankrunProvider(context);
+  const program = new Program<Lending2>(IDL as Lending2, provider);
+  const banksClient = context.banksClient;
+  const borrower = provider.wallet.payer;
+  const liquidator = Keypair.generate();
+
+  const lendingMarket = Keypair.generate();
+
+  const mintUSDC = await createMint(
+    // @ts-ignore
+    banksClient,
+    borrower,
+    borrower.publicKey,
+    null,
+    6
+  );
+  const mintSOL = await createMint(
+    // @ts-ignore
+    banksClient,
+    borrower,
+    borrower.publicKey,
+    null,
+    9
+  );
+
+  const {
+    bankAccounts,
+    userAccountFor,
+    userTokenAccountFor,
+    initLendingMarket,
+    initBank,
+    updateBankConfig,
+    initUserTokenAccount,
+    fundUser,
+    deposit,
+    healthAccounts,
+    borrow,
+  } = marketFixture(program, banksClient, borrower, lendingMarket);
+
+  const usdc = bankAccounts(mintUSDC);
+  const sol = bankAccounts(mintSOL);
+
+  const setSolPrice = (price: number) => updateBankConfig(mintSOL, { oracleSetup: fixedPrice(price) });
+
+  const fundAndDeposit = async (owner: Keypair, mint: PublicKey, amount: number) => {
+    await fundUser(owner.publicKey, mint, 2 * amount);
+    await deposit(owner, mint, amount);
+  };
+
+  // Borrower positions in order: SOL, then USDC
+  const borrowerHealthAccounts = () => healthAccounts(borrower.publicKey, [mintSOL, mintUSDC]);
+
+  const liquidate = (amount: number) =>
+    program.methods
+      .liquidate(new BN(amount))
+      .accountsPartial({
+        liquidator: liquidator.publicKey,
+        borrower: borrower.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        collateralMint: mintSOL,
+        borrowedMint: mintUSDC,
+        userAccount: userAccountFor(borrower.publicKey),
+        collateralBank: sol.bank,
+        collateralBankTokenAccount: sol.bankTokenAccount,
+        borrowedBank: usdc.bank,
+        borrowedBankTokenAccount: usdc.bankTokenAccount,
+        userCollateralTokenAccount: userTokenAccountFor(borrower.publicKey, mintSOL),
+        userBorrowedTokenAccount: userTokenAccountFor(borrower.publicKey, mintUSDC),
+        liquidatorCollateralTokenAccount: liquidatorSolAta,
+        liquidatorBorrowedTokenAccount: liquidatorUsdcAta,
+        priceUpdateCollateral: sol.bank,
+        priceUpdateBorrowed: usdc.bank,
+        fallbackPriceUpdateCollateral: null,
+        fallbackPriceUpdateBorrowed: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(borrowerHealthAccounts())
+      .signers([liquidator])
+      .rpc({ commitment: 'confirmed' });
+
+  const tokenBalance = async (address: PublicKey) =>
+    (
+      await getAccount(
+        // @ts-ignore
+        banksClient,
+        address
+      )
+    ).amount;
+
+  const liquidatorSolAta = getAssociatedTokenAddressSync(mintSOL, liquidator.publicKey);
+  const liquidatorUsdcAta = getAssociatedTokenAddressSync(mintUSDC, liquidator.publicKey);
+
+  const openPositions = async () => {
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: borrower.publicKey,
+          toPubkey: liquidator.publicKey,
+          lamports: 10 * LAMPORTS_PER_SOL,
+        })
+      )
+    );
+
+    await initLendingMarket();
+
+    await initBank(mintUSDC, fixedPrice(1));
+    await initBank(mintSOL, fixedPrice(100));
+
+    // The liquidator provides the USDC the borrower takes out
+    await initUserTokenAccount(liquidator, mintUSDC);
+    await fundAndDeposit(liquidator, mintUSDC, 5_000 * 10 ** 6);
+
+    // 10 SOL at $100 lets the borrower take up to $750
+    await initUserTokenAccount(borrower, mintSOL);
+    await initUserTokenAccount(borrower, mintUSDC);
+    await fundAndDeposit(borrower, mintSOL, 10 * 10 ** 9);
+
+    await borrow(borrower, mintUSDC, 700 * 10 ** 6, [mintSOL, mintUSDC]);
+  };
+
+  return {
+    program,
+    borrower,
+    liquidator,
+    mintSOL,
+    mintUSDC,
+    usdc,
+    sol,
+    userTokenAccountFor,
+    initUserTokenAccount,
+    borrow,
+    setSolPrice,
+    liquidate,
+    tokenBalance,
+    liquidatorSolAta,
+    liquidatorUsdcAta,
+    openPositions,
+  };
+};
+
+describe('Liquidation Tests', async () => {
+  const {
+    program,
+    borrower,
+    mintSOL,
+    usdc,
+    userTokenAccountFor,
+    setSolPrice,
+    liquidate,
+    tokenBalance,
+    liquidatorSolAta,
+    liquidatorUsdcAta,
+    openPositions,
+  } = await liquidationMarket();
+
+  it('Init Market, Banks and Positions', async () => {
+    await openPositions();
+  });
+
+  it('Reject Liquidating a Healthy Position', async () => {
+    // $1000 * 80% threshold is above the $700 debt
+    await assert.rejects(liquidate(100 * 10 ** 6), /NotUnderCollateralized/);
+  });
+
+  it('Liquidate Up to the Close Factor With Bonus', async () => {
+    // $800 * 80% threshold is below the $700 debt
+    await setSolPrice(80);
+
+    const usdcBefore = await tokenBalance(liquidatorUsdcAta);
+    await liquidate(1_000 * 10 ** 6);
+
+    // 50% close factor: only 350 of the 1000 USDC offered are repaid
+    const usdcPaid = usdcBefore - (await tokenBalance(liquidatorUsdcAta));
+    assert.equal(usdcPaid.toString(), (350 * 10 ** 6).toString());
+
+    const bankData = await program.account.bank.fetch(usdc.bank);
+    assert.equal(bankData.totalBorrowed.toString(), (350 * 10 ** 6).toString());
+
+    // $350 plus the 5% bonus at $80: 4.59375 SOL
+    assert.equal((await tokenBalance(liquidatorSolAta)).toString(), '4593750000');
+  });
+
+  it('Liquidation Capped by Collateral Only Charges What It Seizes', async () => {
+    // 5.406249 SOL left at $30 is worth $162.18747, below the 175 USDC close factor
+    await setSolPrice(30);
+
+    const usdcBefore = await tokenBalance(liquidatorUsdcAta);
+    const solBefore = await tokenBalance(liquidatorSolAta);
+    await liquidate(1_000 * 10 ** 6);
+
+    // Repay scaled down to $162.18747 / 1.05
+    const usdcPaid = usdcBefore - (await tokenBalance(liquidatorUsdcAta));
+    assert.equal(usdcPaid.toString(), '154464257');
+
+    // 154.464257 USDC * 1.05 / $30
+    const solSeized = (await tokenBalance(liquidatorSolAta)) - solBefore;
+    assert.equal(solSeized.toString(), '5406248995');
+
+    // Only rounding dust of the collateral is left
+    const collateralData = await program.account.userTokenAccount.fetch(
+      userTokenAccountFor(borrower.publicKey, mintSOL)
+    );
+    assert.ok(collateralData.depositShares.toNumber() < 10);
+  });
+});
+
+describe('Liquidation Liquidity Tests', async () => {
+  const {
+    liquidator,
+    mintSOL,
+    mintUSDC,
+    initUserTokenAccount,
+    borrow,
+    setSolPrice,
+    liquidate,
+    tokenBalance,
+    liquidatorSolAta,
+    liquidatorUsdcAta,
+    openPositions,
+  } = await liquidationMarket();
+
+  it('Init Market, Banks and Positions', async () => {
+    await openPositions();
+
+    // The liquidator lends out 8 of the borrower's 10 SOL, leaving 2 SOL in
+    // the collateral treasury
+    await initUserTokenAccount(liquidator, mintSOL);
+    await borrow(liquidator, mintSOL, 8 * 10 ** 9, [mintUSDC, mintSOL]);
+  });
+
+  it('Liquidation Capped by Available Collateral Liquidity', async () => {
+    await setSolPrice(80);
+
+    const usdcBefore = await tokenBalance(liquidatorUsdcAta);
+    const solBefore = await tokenBalance(liquidatorSolAta);
+    await liquidate(1_000 * 10 ** 6);
+
+    // Repay scaled down to 2 SOL * $80 / 1.05, below the 350 USDC close factor
+    const usdcPaid = usdcBefore - (await tokenBalance(liquidatorUsdcAta));
+    assert.equal(usdcPaid.toString(), '152380952');
+
+    // 152.380952 USDC * 1.05 / $80
+    const solSeized = (await tokenBalance(liquidatorSolAta)) - solBefore;
+    assert.equal(solSeized.toString(), '1999999995');
+
+    // The dust left in the treasury cannot pay for any repayment
+    await assert.rejects(liquidate(100 * 10 ** 6), /InsufficientLiquidationAmount/);
+  });
+});
@@ -0,0 +1,235 @@
+import { BN, Program } from '@coral-xyz/anchor';
+import { Keypair, PublicKey } from '@solana/web3.js';
+import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
+import { BanksClient } from 'solana-bankrun';
+import { createAssociatedTokenAccount, mintTo } from 'spl-token-bankrun';
+
+import { Lending2 } from '../target/types/lending2';
+
+// `update_bank_config` arguments that leave every setting unchanged
+export const UNCHANGED_BANK_CONFIG = {
+  liquidationThreshold: null,
+  maxLtv: null,
+  liquidationBonus: null,
+  liquidationCloseFactor: null,
+  interestRateModel: null,
+  priceFeedId: null,
+  depositLimit: null,
+  borrowLimit: null,
+  depositLimitUsd: null,
+  borrowLimitUsd: null,
+  reserveFactor: null,
+  flashLoanFee: null,
+  maxPriceAge: null,
+  maxConfidenceBps: null,
+  priceMode: null,
+  oracleSetup: null,
+  oracle: null,
+  fallbackOracle: null,
+  removeFallbackOracle: null,
+  maxOracleDivergenceBps: null,
+  maxLstRateChangeBps: null,
+};
+
+export type BankConfig = { [Field in keyof typeof UNCHANGED_BANK_CONFIG]: any };
+
+export const fixedPrice = (price: number, exponent = 0) => ({
+  fixed: { price: new BN(price), exponent },
+});
+
+export const DEFAULT_INTEREST_RATE_MODEL = {
+  baseRate: new BN(0),
+  optimalUtilization: new BN(8000),
+  slope1: new BN(500),
+  slope2: new BN(7500),
+};
+
+// Accounts and instructions of one lending market, owned by `owner`, who also
+// pays for and mints every test token
+export const marketFixture = (
+  program: Program<Lending2>,
+  banksClient: BanksClient,
+  owner: Keypair,
+  lendingMarket: Keypair
+) => {
+  const pda = (seeds: Buffer[]) =>
+    PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+
+  const bankAccounts = (mint: PublicKey) => ({
+    bank: pda([lendingMarket.publicKey.toBuffer(), mint.toBuffer()]),
+    bankTokenAccount: pda([
+      Buffer.from('treasury'),
+      lendingMarket.publicKey.toBuffer(),
+      mint.toBuffer(),
+    ]),
+  });
+
+  const userAccountFor = (user: PublicKey) =>
+    pda([Buffer.from('user'), lendingMarket.publicKey.toBuffer(), user.toBuffer()]);
+
+  const userTokenAccountFor = (user: PublicKey, mint: PublicKey) =>
+    pda([
+      Buffer.from('user-token'),
+      lendingMarket.publicKey.toBuffer(),
+      user.toBuffer(),
+      mint.toBuffer(),
+    ]);
+
+  const initLendingMarket = (guardian = owner.publicKey) =>
+    program.methods
+      .initLendingMarket(guardian, owner.publicKey)
+      .accounts({
+        owner: owner.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+      })
+      .signers([lendingMarket])
+      .rpc({ commitment: 'confirmed' });
+
+  const initBank = (
+    mint: PublicKey,
+    oracleSetup: object = fixedPrice(1),
+    tokenProgram = TOKEN_PROGRAM_ID
+  ) =>
+    program.methods
+      .initBank({
+        liquidationThreshold: new BN(8000),
+        maxLtv: new BN(7500),
+        liquidationBonus: new BN(500),
+        liquidationCloseFactor: new BN(5000),
+        interestRateModel: DEFAULT_INTEREST_RATE_MODEL,
+        // @ts-ignore
+        oracleSetup,
+        oracle: PublicKey.default,
+        priceFeedId: '',
+      })
+      .accountsPartial({
+        owner: owner.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint,
+        ...bankAccounts(mint),
+        tokenProgram,
+      })
+      .rpc({ commitment: 'confirmed' });
+
+  const updateBankConfig = (mint: PublicKey, changes: Partial<BankConfig>) =>
+    program.methods
+      .updateBankConfig({ ...UNCHANGED_BANK_CONFIG, ...changes })
+      .accountsPartial({
+        owner: owner.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint,
+        bank: bankAccounts(mint).bank,
+      })
+      .rpc({ commitment: 'confirmed' });
+
+  const initUserTokenAccount = (user: Keypair, mint: PublicKey) =>
+    program.methods
+      .initUserTokenAccount('TOKEN')
+      .accountsPartial({
+        signer: user.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint,
+        userTokenAccount: userTokenAccountFor(user.publicKey, mint),
+      })
+      .signers([user])
+      .rpc({ commitment: 'confirmed' });
+
+  // Creates `user`'s associated token account holding `amount`
+  const fundUser = async (user: PublicKey, mint: PublicKey, amount: number) => {
+    const userAta = await createAssociatedTokenAccount(
+      // @ts-ignore
+      banksClient,
+      owner,
+      mint,
+      user
+    );
+    // @ts-ignore
+    await mintTo(banksClient, owner, mint, userAta, owner, amount);
+    return userAta;
+  };
+
+  // Deposits from `user`'s associated token account into a fixed-price bank
+  const deposit = (user: Keypair, mint: PublicKey, amount: number) => {
+    const accounts = bankAccounts(mint);
+    return program.methods
+      .deposit(new BN(amount))
+      .accountsPartial({
+        signer: user.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint,
+        userAccount: userAccountFor(user.publicKey),
+        ...accounts,
+        userTokenAccount: userTokenAccountFor(user.publicKey, mint),
+        // Ignored by fixed-price banks
+        priceUpdate: accounts.bank,
+        fallbackPriceUpdate: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc({ commitment: 'confirmed' });
+  };
+
+  // Health check accounts of `user`'s positions in fixed-price banks, in order
+  const healthAccounts = (user: PublicKey, mints: PublicKey[]) =>
+    mints.flatMap((mint) =>
+      [
+        bankAccounts(mint).bank,
+        userTokenAccountFor(user, mint),
+        bankAccounts(mint).bank,
+      ].map((pubkey) => ({ pubkey, isSigner: false, isWritable: false }))
+    );
+
+  // Borrows from a fixed-price bank, `positions` are the mints of the user's
+  // positions after the borrow, in order
+  const borrow = (user: Keypair, mint: PublicKey, amount: number, positions: PublicKey[]) => {
+    const accounts = bankAccounts(mint);
+    return program.methods
+      .borrow(new BN(amount))
+      .accountsPartial({
+        signer: user.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mintBorrow: mint,
+        userAccount: userAccountFor(user.publicKey),
+        bankBorrow: accounts.bank,
+        bankTokenAccountBorrow: accounts.bankTokenAccount,
+        userTokenAccountBorrow: userTokenAccountFor(user.publicKey, mint),
+        priceUpdate: accounts.bank,
+        fallbackPriceUpdate: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(healthAccounts(user.publicKey, positions))
+      .signers([user])
+      .rpc({ commitment: 'confirmed' });
+  };
+
+  const repay = (user: Keypair, mint: PublicKey, amount: number) =>
+    program.methods
+      .repay(new BN(amount))
+      .accountsPartial({
+        signer: user.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint,
+        ...bankAccounts(mint),
+        userAccount: userAccountFor(user.publicKey),
+        userTokenAccount: userTokenAccountFor(user.publicKey, mint),
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc({ commitment: 'confirmed' });
+
+  return {
+    pda,
+    bankAccounts,
+    userAccountFor,
+    userTokenAccountFor,
+    initLendingMarket,
+    initBank,
+    updateBankConfig,
+    initUserTokenAccount,
+    fundUser,
+    deposit,
+    healthAccounts,
+    borrow,
+    repay,
+  };
+};
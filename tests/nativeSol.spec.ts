@@ -0,0 +1,225 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+// @ts-ignore
+import IDL from '../target/idl/lending2.json';
+import { Lending2 } from '../target/types/lending2';
+import { startAnchor } from 'solana-bankrun';
+import { BankrunProvider } from 'anchor-bankrun';
+import { BN, Program } from '@coral-xyz/anchor';
+import { Keypair, LAMPORTS_PER_SOL, PublicKey, SystemProgram, Transaction } from '@solana/web3.js';
+import { getAccount } from 'spl-token-bankrun';
+import {
+  MINT_SIZE,
+  MintLayout,
+  NATIVE_MINT,
+  TOKEN_PROGRAM_ID,
+  createAssociatedTokenAccountIdempotentInstruction,
+  createSyncNativeInstruction,
+  getAssociatedTokenAddressSync,
+} from '@solana/spl-token';
+
+// SOL is wrapped and unwrapped by the program, no wSOL account is kept
+describe('Native SOL Tests', async () => {
+  const context = await startAnchor(
+    '',
+    [{ name: 'lending2', programId: new PublicKey(IDL.address) }],
+    []
+  );
+  const provider = new BankrunProvider(context);
+  const program = new Program<Lending2>(IDL as Lending2, provider);
+  const banksClient = context.banksClient;
+  const signer = provider.wallet.payer;
+
+  const lendingMarket = Keypair.generate();
+
+  // Make sure the native mint exists in the test validator
+  if (!(await banksClient.getAccount(NATIVE_MINT))) {
+    const data = Buffer.alloc(MINT_SIZE);
+    MintLayout.encode(
+      {
+        mintAuthorityOption: 0,
+        mintAuthority: PublicKey.default,
+        supply: BigInt(0),
+        decimals: 9,
+        isInitialized: true,
+        freezeAuthorityOption: 0,
+        freezeAuthority: PublicKey.default,
+      },
+      data
+    );
+    context.setAccount(NATIVE_MINT, {
+      lamports: LAMPORTS_PER_SOL,
+      data,
+      owner: TOKEN_PROGRAM_ID,
+      executable: false,
+    });
+  }
+
+  const pda = (seeds: Buffer[]) =>
+    PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+
+  const bank = pda([lendingMarket.publicKey.toBuffer(), NATIVE_MINT.toBuffer()]);
+  const bankTokenAccount = pda([
+    Buffer.from('treasury'),
+    lendingMarket.publicKey.toBuffer(),
+    NATIVE_MINT.toBuffer(),
+  ]);
+  const userTokenAccount = pda([
+    Buffer.from('user-token'),
+    lendingMarket.publicKey.toBuffer(),
+    signer.publicKey.toBuffer(),
+    NATIVE_MINT.toBuffer(),
+  ]);
+  const userAccount = pda([
+    Buffer.from('user'),
+    lendingMarket.publicKey.toBuffer(),
+    signer.publicKey.toBuffer(),
+  ]);
+  const wsolAccount = getAssociatedTokenAddressSync(NATIVE_MINT, signer.publicKey);
+
+  const accounts = {
+    signer: signer.publicKey,
+    lendingMarket: lendingMarket.publicKey,
+    mint: NATIVE_MINT,
+    userAccount,
+    bank,
+    bankTokenAccount,
+    userTokenAccount,
+    // Ignored by fixed-price banks
+    priceUpdate: bank,
+    fallbackPriceUpdate: null,
+    tokenProgram: TOKEN_PROGRAM_ID,
+  };
+
+  const withdrawSol = (amount: number) => {
+    const temporaryWsolAccount = Keypair.generate();
+    return program.methods
+      .withdrawSol(new BN(amount))
+      .accountsPartial({
+        withdraw: {
+          ...accounts,
+          userAssociatedTokenAccount: wsolAccount,
+        },
+        temporaryWsolAccount: temporaryWsolAccount.publicKey,
+      })
+      .remainingAccounts(
+        [bank, userTokenAccount, bank].map((pubkey) => ({
+          pubkey,
+          isSigner: false,
+          isWritable: false,
+        }))
+      )
+      .signers([temporaryWsolAccount])
+      .rpc({ commitment: 'confirmed' });
+  };
+
+  it('Init Market and SOL Bank', async () => {
+    await program.methods
+      .initLendingMarket(signer.publicKey, signer.publicKey)
+      .accounts({
+        owner: signer.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+      })
+      .signers([lendingMarket])
+      .rpc({ commitment: 'confirmed' });
+
+    await program.methods
+      .initBank({
+        liquidationThreshold: new BN(8000),
+        maxLtv: new BN(7500),
+        liquidationBonus: new BN(500),
+        liquidationCloseFactor: new BN(5000),
+        interestRateModel: {
+          baseRate: new BN(0),
+          optimalUtilization: new BN(8000),
+          slope1: new BN(500),
+          slope2: new BN(7500),
+        },
+        oracleSetup: { fixed: { price: new BN(150), exponent: 0 } },
+        oracle: PublicKey.default,
+        priceFeedId: '',
+      })
+      .accountsPartial({
+        owner: signer.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint: NATIVE_MINT,
+        bank,
+        bankTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc({ commitment: 'confirmed' });
+
+    await program.methods
+      .initUserTokenAccount('SOL')
+      .accountsPartial({
+        signer: signer.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint: NATIVE_MINT,
+        userTokenAccount,
+      })
+      .rpc({ commitment: 'confirmed' });
+  });
+
+  it('Deposit Native SOL', async () => {
+    await program.methods
+      .depositSol(new BN(2 * LAMPORTS_PER_SOL))
+      .accountsPartial({
+        ...accounts,
+        userTokenAssociatedAccount: wsolAccount,
+      })
+      .rpc({ commitment: 'confirmed' });
+
+    const bankData = await program.account.bank.fetch(bank);
+    assert.equal(bankData.totalDeposits.toString(), (2 * LAMPORTS_PER_SOL).toString());
+
+    // The temporary wSOL account is closed again
+    assert.equal(await banksClient.getAccount(wsolAccount), null);
+  });
+
+  it('Withdraw Native SOL', async () => {
+    const balanceBefore = await banksClient.getBalance(signer.publicKey);
+
+    await withdrawSol(LAMPORTS_PER_SOL / 2);
+
+    const balanceAfter = await banksClient.getBalance(signer.publicKey);
+    // Only the transaction fees are paid on top of the unwrapped lamports
+    assert.ok(balanceAfter - balanceBefore > BigInt(LAMPORTS_PER_SOL / 2 - 20_000));
+
+    const bankData = await program.account.bank.fetch(bank);
+    assert.equal(bankData.totalDeposits.toString(), (1.5 * LAMPORTS_PER_SOL).toString());
+    assert.equal(await banksClient.getAccount(wsolAccount), null);
+  });
+
+  it('Withdraw Native SOL Keeps Existing wSOL', async () => {
+    // Wrap some SOL the user keeps as wSOL
+    const transaction = new Transaction().add(
+      createAssociatedTokenAccountIdempotentInstruction(
+        signer.publicKey,
+        wsolAccount,
+        signer.publicKey,
+        NATIVE_MINT
+      ),
+      SystemProgram.transfer({
+        fromPubkey: signer.publicKey,
+        toPubkey: wsolAccount,
+        lamports: LAMPORTS_PER_SOL / 4,
+      }),
+      createSyncNativeInstruction(wsolAccount)
+    );
+    await provider.sendAndConfirm(transaction);
+
+    const balanceBefore = await banksClient.getBalance(signer.publicKey);
+    await withdrawSol(LAMPORTS_PER_SOL / 2);
+
+    const balanceAfter = await banksClient.getBalance(signer.publicKey);
+    assert.ok(balanceAfter - balanceBefore > BigInt(LAMPORTS_PER_SOL / 2 - 20_000));
+
+    // Only the withdrawn amount is unwrapped
+    const wsolData = await getAccount(
+      // @ts-ignore
+      banksClient,
+      wsolAccount
+    );
+    assert.equal(wsolData.amount.toString(), (LAMPORTS_PER_SOL / 4).toString());
+  });
+});
@@ -0,0 +1,127 @@
+import { PublicKey } from '@solana/web3.js';
+
+// Switchboard On-Demand program, owner of pull feed accounts
+export const SWITCHBOARD_ON_DEMAND_PROGRAM_ID = new PublicKey(
+  'SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv'
+);
+
+// sha256("account:PullFeedAccountData")[..8]
+const PULL_FEED_DISCRIMINATOR = Buffer.from([196, 27, 108, 196, 10, 215, 219, 40]);
+
+// Offsets in `PullFeedAccountData`, discriminator included
+const LAST_UPDATE_TIMESTAMP_OFFSET = 2216;
+const RESULT_VALUE_OFFSET = 2264;
+const RESULT_STD_DEV_OFFSET = 2280;
+const RESULT_NUM_SAMPLES_OFFSET = 2360;
+const PULL_FEED_ACCOUNT_SIZE = 3720;
+
+const WAD = BigInt(10) ** BigInt(18);
+
+// Builds a Switchboard pull feed account holding `price` (USD, up to 18 decimals)
+export function buildSwitchboardPullFeed(
+  price: number,
+  stdDev: number,
+  lastUpdateTimestamp: bigint
+) {
+  const data = Buffer.alloc(PULL_FEED_ACCOUNT_SIZE);
+  PULL_FEED_DISCRIMINATOR.copy(data, 0);
+  data.writeBigInt64LE(lastUpdateTimestamp, LAST_UPDATE_TIMESTAMP_OFFSET);
+  writeI128(data, toWad(price), RESULT_VALUE_OFFSET);
+  writeI128(data, toWad(stdDev), RESULT_STD_DEV_OFFSET);
+  data.writeUInt8(1, RESULT_NUM_SAMPLES_OFFSET);
+
+  return {
+    lamports: 1_000_000_000,
+    data,
+    owner: SWITCHBOARD_ON_DEMAND_PROGRAM_ID,
+    executable: false,
+  };
+}
+
+function toWad(value: number): bigint {
+  return BigInt(Math.round(value * 1e9)) * (WAD / BigInt(1e9));
+}
+
+function writeI128(data: Buffer, value: bigint, offset: number) {
+  const unsigned = BigInt.asUintN(128, value);
+  data.writeBigUInt64LE(unsigned & BigInt('0xffffffffffffffff'), offset);
+  data.writeBigUInt64LE(unsigned >> BigInt(64), offset + 8);
+}
+
+// Offsets in the SPL `StakePool` account
+const STAKE_POOL_MINT_OFFSET = 162;
+const STAKE_POOL_TOTAL_LAMPORTS_OFFSET = 258;
+const STAKE_POOL_TOKEN_SUPPLY_OFFSET = 266;
+const STAKE_POOL_LAST_UPDATE_EPOCH_OFFSET = 274;
+const STAKE_POOL_ACCOUNT_SIZE = 611;
+
+// Builds a stake pool account for `poolMint`, owned by `programId`
+export function buildStakePool(
+  programId: PublicKey,
+  poolMint: PublicKey,
+  totalLamports: bigint,
+  poolTokenSupply: bigint,
+  lastUpdateEpoch: bigint
+) {
+  const data = Buffer.alloc(STAKE_POOL_ACCOUNT_SIZE);
+  // AccountType::StakePool
+  data.writeUInt8(1, 0);
+  poolMint.toBuffer().copy(data, STAKE_POOL_MINT_OFFSET);
+  data.writeBigUInt64LE(totalLamports, STAKE_POOL_TOTAL_LAMPORTS_OFFSET);
+  data.writeBigUInt64LE(poolTokenSupply, STAKE_POOL_TOKEN_SUPPLY_OFFSET);
+  data.writeBigUInt64LE(lastUpdateEpoch, STAKE_POOL_LAST_UPDATE_EPOCH_OFFSET);
+
+  return {
+    lamports: 1_000_000_000,
+    data,
+    owner: programId,
+    executable: false,
+  };
+}
+
+// Pyth Solana Receiver program, owner of `PriceUpdateV2` accounts
+export const PYTH_RECEIVER_PROGRAM_ID = new PublicKey(
+  'rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ'
+);
+
+// sha256("account:PriceUpdateV2")[..8]
+const PRICE_UPDATE_V2_DISCRIMINATOR = Buffer.from([34, 241, 35, 99, 157, 126, 244, 205]);
+const PRICE_UPDATE_V2_ACCOUNT_SIZE = 134;
+
+// Builds a fully verified Pyth price update for `feedId` (hex, with or
+// without 0x), prices are `value * 10^exponent`
+export function buildPythPriceUpdate(
+  feedId: string,
+  price: bigint,
+  conf: bigint,
+  emaPrice: bigint,
+  emaConf: bigint,
+  exponent: number,
+  publishTime: bigint
+) {
+  const data = Buffer.alloc(PRICE_UPDATE_V2_ACCOUNT_SIZE);
+  let offset = 0;
+  PRICE_UPDATE_V2_DISCRIMINATOR.copy(data, offset);
+  // Zeroed write authority
+  offset += 8 + 32;
+  // VerificationLevel::Full
+  data.writeUInt8(1, offset);
+  offset += 1;
+  Buffer.from(feedId.replace(/^0x/, ''), 'hex').copy(data, offset);
+  offset += 32;
+  data.writeBigInt64LE(price, offset);
+  data.writeBigUInt64LE(conf, offset + 8);
+  data.writeInt32LE(exponent, offset + 16);
+  data.writeBigInt64LE(publishTime, offset + 20);
+  // prev_publish_time
+  data.writeBigInt64LE(publishTime, offset + 28);
+  data.writeBigInt64LE(emaPrice, offset + 36);
+  data.writeBigUInt64LE(emaConf, offset + 44);
+
+  return {
+    lamports: 1_000_000_000,
+    data,
+    owner: PYTH_RECEIVER_PROGRAM_ID,
+    executable: false,
+  };
+}
@@ -0,0 +1,161 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+// @ts-ignore
+import IDL from '../target/idl/lending2.json';
+import { Lending2 } from '../target/types/lending2';
+import { startAnchor } from 'solana-bankrun';
+import { BankrunProvider } from 'anchor-bankrun';
+import { Program } from '@coral-xyz/anchor';
+import { Keypair, PublicKey } from '@solana/web3.js';
+import { createMint } from 'spl-token-bankrun';
+
+import { fixedPrice, marketFixture } from './marketFixture';
+
+// Bits of `pause_flags`
+const PAUSE_DEPOSITS = 1 << 0;
+const PAUSE_BORROWS = 1 << 1;
+const PAUSE_WITHDRAWALS = 1 << 2;
+const PAUSE_FROZEN = 1 << 4;
+
+describe('Pause Flag Tests', async () => {
+  const context = await startAnchor(
+    '',
+    [{ name: 'lending2', programId: new PublicKey(IDL.address) }],
+    []
+  );
+  const provider = new BankrunProvider(context);
+  const program = new Program<Lending2>(IDL as Lending2, provider);
+  const banksClient = context.banksClient;
+  const owner = provider.wallet.payer;
+  const guardian = Keypair.generate();
+
+  const lendingMarket = Keypair.generate();
+
+  const mintUSDC = await createMint(
+    // @ts-ignore
+    banksClient,
+    owner,
+    owner.publicKey,
+    null,
+    6
+  );
+  const mintSOL = await createMint(
+    // @ts-ignore
+    banksClient,
+    owner,
+    owner.publicKey,
+    null,
+    9
+  );
+
+  const {
+    bankAccounts,
+    initLendingMarket,
+    initBank,
+    initUserTokenAccount,
+    fundUser,
+    deposit,
+    borrow,
+    repay,
+  } = marketFixture(program, banksClient, owner, lendingMarket);
+
+  const setMarketPauseFlags = (authority: Keypair, pauseFlags: number) =>
+    program.methods
+      .setMarketPauseFlags(pauseFlags)
+      .accounts({
+        authority: authority.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+      })
+      .signers([authority])
+      .rpc({ commitment: 'confirmed' });
+
+  const setBankPauseFlags = (authority: Keypair, mint: PublicKey, pauseFlags: number) =>
+    program.methods
+      .setBankPauseFlags(pauseFlags)
+      .accountsPartial({
+        authority: authority.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint,
+        bank: bankAccounts(mint).bank,
+      })
+      .signers([authority])
+      .rpc({ commitment: 'confirmed' });
+
+  const marketPauseFlags = async () =>
+    (await program.account.lendingMarket.fetch(lendingMarket.publicKey)).pauseFlags;
+
+  // Positions in order: SOL, then USDC
+  const borrowUSDC = (amount: number) => borrow(owner, mintUSDC, amount, [mintSOL, mintUSDC]);
+
+  it('Init Market, Banks and Positions', async () => {
+    await initLendingMarket(guardian.publicKey);
+
+    await initBank(mintUSDC, fixedPrice(1));
+    await initBank(mintSOL, fixedPrice(100));
+
+    await initUserTokenAccount(owner, mintSOL);
+    await initUserTokenAccount(owner, mintUSDC);
+    await fundUser(owner.publicKey, mintSOL, 20 * 10 ** 9);
+    await fundUser(owner.publicKey, mintUSDC, 2_000 * 10 ** 6);
+
+    await deposit(owner, mintSOL, 10 * 10 ** 9);
+    await deposit(owner, mintUSDC, 1_000 * 10 ** 6);
+    await borrowUSDC(100 * 10 ** 6);
+  });
+
+  it('Reject Pause Flags From Another Signer', async () => {
+    await assert.rejects(setMarketPauseFlags(Keypair.generate(), PAUSE_DEPOSITS), /Unauthorized/);
+  });
+
+  it('Reject Unknown Pause Flags', async () => {
+    await assert.rejects(setMarketPauseFlags(owner, 1 << 5), /InvalidPauseFlags/);
+  });
+
+  it('Guardian Pauses Market Deposits', async () => {
+    await setMarketPauseFlags(guardian, PAUSE_DEPOSITS);
+    assert.equal(await marketPauseFlags(), PAUSE_DEPOSITS);
+
+    await assert.rejects(deposit(owner, mintUSDC, 10 ** 6), /DepositsPaused/);
+  });
+
+  it('Guardian Can Only Add Flags', async () => {
+    await setMarketPauseFlags(guardian, PAUSE_DEPOSITS | PAUSE_WITHDRAWALS);
+
+    // Dropping PAUSE_DEPOSITS would unpause deposits
+    await assert.rejects(setMarketPauseFlags(guardian, PAUSE_WITHDRAWALS), /Unauthorized/);
+    await assert.rejects(setMarketPauseFlags(guardian, 0), /Unauthorized/);
+    await assert.rejects(setBankPauseFlags(guardian, mintUSDC, 0), /Unauthorized/);
+    assert.equal(await marketPauseFlags(), PAUSE_DEPOSITS | PAUSE_WITHDRAWALS);
+  });
+
+  it('Owner Unpauses the Market', async () => {
+    await setMarketPauseFlags(owner, 0);
+    assert.equal(await marketPauseFlags(), 0);
+
+    await deposit(owner, mintUSDC, 2 * 10 ** 6);
+  });
+
+  it('Bank Flags Only Pause That Bank', async () => {
+    await setBankPauseFlags(guardian, mintUSDC, PAUSE_BORROWS);
+
+    await assert.rejects(borrowUSDC(10 ** 6), /BorrowsPaused/);
+    // Deposits into the same bank are still open
+    await deposit(owner, mintUSDC, 3 * 10 ** 6);
+
+    await setBankPauseFlags(owner, mintUSDC, 0);
+    await borrowUSDC(2 * 10 ** 6);
+  });
+
+  it('Market Freeze Blocks Repayments', async () => {
+    await setMarketPauseFlags(guardian, PAUSE_FROZEN);
+
+    await assert.rejects(repay(owner, mintUSDC, 10 ** 6), /BankFrozen/);
+    await assert.rejects(deposit(owner, mintSOL, 10 ** 9), /BankFrozen/);
+
+    // A new slot, so the earlier unpause transaction is not deduplicated
+    const { slot } = await banksClient.getClock();
+    context.warpToSlot(slot + BigInt(1));
+    await setMarketPauseFlags(owner, 0);
+    await repay(owner, mintUSDC, 2 * 10 ** 6);
+  });
+});
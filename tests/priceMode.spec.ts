@@ -0,0 +1,179 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+// @ts-ignore
+import IDL from '../target/idl/lending2.json';
+import { Lending2 } from '../target/types/lending2';
+import { startAnchor } from 'solana-bankrun';
+import { BankrunProvider } from 'anchor-bankrun';
+import { BN, Program } from '@coral-xyz/anchor';
+import { Keypair, LAMPORTS_PER_SOL, PublicKey, SystemProgram, Transaction } from '@solana/web3.js';
+import { createMint } from 'spl-token-bankrun';
+import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
+
+import { fixedPrice, marketFixture } from './marketFixture';
+import { buildPythPriceUpdate } from './oracleAccounts';
+
+const SOL_PRICE_FEED_ID =
+  '0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d';
+
+// SOL is priced by a locally built Pyth update with a $100 spot and an $80
+// EMA, the borrower's 10 SOL back up to $750 at spot and $600 at EMA
+describe('Pyth Price Mode Tests', async () => {
+  const context = await startAnchor(
+    '',
+    [{ name: 'lending2', programId: new PublicKey(IDL.address) }],
+    []
+  );
+  const provider = new BankrunProvider(context);
+  const program = new Program<Lending2>(IDL as Lending2, provider);
+  const banksClient = context.banksClient;
+  const lender = provider.wallet.payer;
+  const borrower = Keypair.generate();
+
+  const lendingMarket = Keypair.generate();
+  const solPriceUpdate = Keypair.generate().publicKey;
+
+  const mintUSDC = await createMint(
+    // @ts-ignore
+    banksClient,
+    lender,
+    lender.publicKey,
+    null,
+    6
+  );
+  const mintSOL = await createMint(
+    // @ts-ignore
+    banksClient,
+    lender,
+    lender.publicKey,
+    null,
+    9
+  );
+
+  const {
+    bankAccounts,
+    userAccountFor,
+    userTokenAccountFor,
+    initLendingMarket,
+    initBank,
+    updateBankConfig,
+    initUserTokenAccount,
+    fundUser,
+    deposit,
+  } = marketFixture(program, banksClient, lender, lendingMarket);
+
+  const usdc = bankAccounts(mintUSDC);
+  const sol = bankAccounts(mintSOL);
+
+  const setPriceMode = (priceMode: object) => updateBankConfig(mintSOL, { priceMode });
+
+  const depositSOL = (amount: number) =>
+    program.methods
+      .deposit(new BN(amount))
+      .accountsPartial({
+        signer: borrower.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint: mintSOL,
+        userAccount: userAccountFor(borrower.publicKey),
+        ...sol,
+        userTokenAccount: userTokenAccountFor(borrower.publicKey, mintSOL),
+        priceUpdate: solPriceUpdate,
+        fallbackPriceUpdate: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([borrower])
+      .rpc({ commitment: 'confirmed' });
+
+  // Borrower positions in order: SOL, then USDC
+  const borrowUSDC = (amount: number) =>
+    program.methods
+      .borrow(new BN(amount))
+      .accountsPartial({
+        signer: borrower.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mintBorrow: mintUSDC,
+        userAccount: userAccountFor(borrower.publicKey),
+        bankBorrow: usdc.bank,
+        bankTokenAccountBorrow: usdc.bankTokenAccount,
+        userTokenAccountBorrow: userTokenAccountFor(borrower.publicKey, mintUSDC),
+        priceUpdate: usdc.bank,
+        fallbackPriceUpdate: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(
+        [
+          sol.bank,
+          userTokenAccountFor(borrower.publicKey, mintSOL),
+          solPriceUpdate,
+          usdc.bank,
+          userTokenAccountFor(borrower.publicKey, mintUSDC),
+          usdc.bank,
+        ].map((pubkey) => ({ pubkey, isSigner: false, isWritable: false }))
+      )
+      .signers([borrower])
+      .rpc({ commitment: 'confirmed' });
+
+  it('Init Market, Banks and Positions', async () => {
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: lender.publicKey,
+          toPubkey: borrower.publicKey,
+          lamports: 10 * LAMPORTS_PER_SOL,
+        })
+      )
+    );
+
+    // 8 decimal prices, as published for SOL/USD
+    const { unixTimestamp } = await banksClient.getClock();
+    context.setAccount(
+      solPriceUpdate,
+      buildPythPriceUpdate(
+        SOL_PRICE_FEED_ID,
+        BigInt(100 * 10 ** 8),
+        BigInt(0),
+        BigInt(80 * 10 ** 8),
+        BigInt(0),
+        -8,
+        unixTimestamp
+      )
+    );
+
+    await initLendingMarket();
+    await initBank(mintUSDC, fixedPrice(1));
+    await initBank(mintSOL, fixedPrice(100));
+    await updateBankConfig(mintSOL, {
+      oracleSetup: { pythPull: {} },
+      priceFeedId: SOL_PRICE_FEED_ID,
+    });
+
+    await initUserTokenAccount(lender, mintUSDC);
+    await fundUser(lender.publicKey, mintUSDC, 2_000 * 10 ** 6);
+    await deposit(lender, mintUSDC, 1_000 * 10 ** 6);
+
+    await initUserTokenAccount(borrower, mintSOL);
+    await initUserTokenAccount(borrower, mintUSDC);
+    await fundUser(borrower.publicKey, mintSOL, 10 * 10 ** 9);
+    await depositSOL(10 * 10 ** 9);
+  });
+
+  it('EMA Mode Values Collateral at the EMA', async () => {
+    await setPriceMode({ ema: {} });
+
+    await assert.rejects(borrowUSDC(700 * 10 ** 6), /OverBorrowableAmount/);
+    await borrowUSDC(500 * 10 ** 6);
+  });
+
+  it('Conservative Mode Values Collateral at the Lower Price', async () => {
+    await setPriceMode({ conservative: {} });
+
+    // $650 of debt is above the $600 backed at the EMA
+    await assert.rejects(borrowUSDC(150 * 10 ** 6), /OverBorrowableAmount/);
+  });
+
+  it('Spot Mode Values Collateral at Spot', async () => {
+    await setPriceMode({ spot: {} });
+
+    await borrowUSDC(150 * 10 ** 6);
+  });
+});
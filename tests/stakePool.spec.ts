@@ -0,0 +1,140 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+// @ts-ignore
+import IDL from '../target/idl/lending2.json';
+import { Lending2 } from '../target/types/lending2';
+import { startAnchor } from 'solana-bankrun';
+import { BankrunProvider } from 'anchor-bankrun';
+import { BN, Program } from '@coral-xyz/anchor';
+import { Keypair, PublicKey } from '@solana/web3.js';
+import { createMint } from 'spl-token-bankrun';
+import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
+
+import { UNCHANGED_BANK_CONFIG } from './marketFixture';
+import { buildStakePool } from './oracleAccounts';
+
+const SOL_PRICE_FEED_ID =
+  '0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d';
+
+// The stake pool account is built locally, only the exchange rate refresh is covered
+describe('Stake Pool LST Oracle Tests', async () => {
+  const context = await startAnchor(
+    '',
+    [{ name: 'lending2', programId: new PublicKey(IDL.address) }],
+    []
+  );
+  const provider = new BankrunProvider(context);
+  const program = new Program<Lending2>(IDL as Lending2, provider);
+  const banksClient = context.banksClient;
+  const signer = provider.wallet.payer;
+
+  const lendingMarket = Keypair.generate();
+  const stakePool = Keypair.generate().publicKey;
+  const stakePoolProgram = Keypair.generate().publicKey;
+
+  const mintLST = await createMint(
+    // @ts-ignore
+    banksClient,
+    signer,
+    signer.publicKey,
+    null,
+    9
+  );
+
+  const [bank] = PublicKey.findProgramAddressSync(
+    [lendingMarket.publicKey.toBuffer(), mintLST.toBuffer()],
+    program.programId
+  );
+
+  const setStakePool = async (totalLamports: bigint, poolTokenSupply: bigint) => {
+    const { epoch } = await banksClient.getClock();
+    context.setAccount(
+      stakePool,
+      buildStakePool(stakePoolProgram, mintLST, totalLamports, poolTokenSupply, epoch)
+    );
+  };
+
+  const refresh = () =>
+    program.methods
+      .refreshLstRate()
+      .accountsPartial({
+        lendingMarket: lendingMarket.publicKey,
+        mint: mintLST,
+        bank,
+        stakePool,
+      })
+      .rpc({ commitment: 'confirmed' });
+
+  it('Init Market and LST Bank', async () => {
+    await program.methods
+      .initLendingMarket(signer.publicKey, signer.publicKey)
+      .accounts({
+        owner: signer.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+      })
+      .signers([lendingMarket])
+      .rpc({ commitment: 'confirmed' });
+
+    await program.methods
+      .initBank({
+        liquidationThreshold: new BN(8000),
+        maxLtv: new BN(7500),
+        liquidationBonus: new BN(500),
+        liquidationCloseFactor: new BN(5000),
+        interestRateModel: {
+          baseRate: new BN(0),
+          optimalUtilization: new BN(8000),
+          slope1: new BN(500),
+          slope2: new BN(7500),
+        },
+        oracleSetup: { stakePool: { stakePool } },
+        oracle: PublicKey.default,
+        priceFeedId: SOL_PRICE_FEED_ID,
+      })
+      .accountsPartial({
+        owner: signer.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint: mintLST,
+        bank,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc({ commitment: 'confirmed' });
+  });
+
+  it('Refresh Exchange Rate', async () => {
+    await setStakePool(BigInt(1_100_000_000), BigInt(1_000_000_000));
+    await refresh();
+
+    const bankData = await program.account.bank.fetch(bank);
+    assert.equal(bankData.lstExchangeRate.toString(), '1100000000000000000');
+  });
+
+  it('Reject Exchange Rate Jump', async () => {
+    // +5% in one refresh, above the default 1% bound
+    await setStakePool(BigInt(1_155_000_000), BigInt(1_000_000_000));
+    await assert.rejects(refresh(), /LstRateChangeTooLarge/);
+  });
+
+  it('Reject Stake Pool Fallback Oracle', async () => {
+    await assert.rejects(
+      program.methods
+        .updateBankConfig({
+          ...UNCHANGED_BANK_CONFIG,
+          fallbackOracle: {
+            setup: { stakePool: { stakePool } },
+            oracle: PublicKey.default,
+            priceFeedId: SOL_PRICE_FEED_ID,
+          },
+          maxOracleDivergenceBps: new BN(500),
+        })
+        .accountsPartial({
+          owner: signer.publicKey,
+          lendingMarket: lendingMarket.publicKey,
+          mint: mintLST,
+          bank,
+        })
+        .rpc({ commitment: 'confirmed' }),
+      /InvalidOracleConfig/
+    );
+  });
+});
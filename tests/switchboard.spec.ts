@@ -0,0 +1,187 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+// @ts-ignore
+import IDL from '../target/idl/lending2.json';
+import { Lending2 } from '../target/types/lending2';
+import { startAnchor } from 'solana-bankrun';
+import { BankrunProvider } from 'anchor-bankrun';
+import { BN, Program } from '@coral-xyz/anchor';
+import { Keypair, PublicKey } from '@solana/web3.js';
+import { createMint, mintTo, createAssociatedTokenAccount } from 'spl-token-bankrun';
+import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
+
+import { BankConfig, UNCHANGED_BANK_CONFIG } from './marketFixture';
+import { buildSwitchboardPullFeed } from './oracleAccounts';
+
+// Runs without network access: the Switchboard feed is built locally
+describe('Switchboard Oracle Tests', async () => {
+  const feed = Keypair.generate().publicKey;
+
+  const context = await startAnchor(
+    '',
+    [{ name: 'lending2', programId: new PublicKey(IDL.address) }],
+    []
+  );
+  const provider = new BankrunProvider(context);
+  const program = new Program<Lending2>(IDL as Lending2, provider);
+  const banksClient = context.banksClient;
+  const signer = provider.wallet.payer;
+
+  const clock = await banksClient.getClock();
+  context.setAccount(feed, buildSwitchboardPullFeed(1.0, 0.001, clock.unixTimestamp));
+
+  const mint = await createMint(
+    // @ts-ignore
+    banksClient,
+    signer,
+    signer.publicKey,
+    null,
+    6
+  );
+
+  const lendingMarket = Keypair.generate();
+
+  const [bank] = PublicKey.findProgramAddressSync(
+    [lendingMarket.publicKey.toBuffer(), mint.toBuffer()],
+    program.programId
+  );
+  const [bankTokenAccount] = PublicKey.findProgramAddressSync(
+    [Buffer.from('treasury'), lendingMarket.publicKey.toBuffer(), mint.toBuffer()],
+    program.programId
+  );
+  const [userAccount] = PublicKey.findProgramAddressSync(
+    [Buffer.from('user'), lendingMarket.publicKey.toBuffer(), signer.publicKey.toBuffer()],
+    program.programId
+  );
+  const [userTokenAccount] = PublicKey.findProgramAddressSync(
+    [
+      Buffer.from('user-token'),
+      lendingMarket.publicKey.toBuffer(),
+      signer.publicKey.toBuffer(),
+      mint.toBuffer(),
+    ],
+    program.programId
+  );
+
+  const deposit = (amount: number) =>
+    program.methods
+      .deposit(new BN(amount))
+      .accountsPartial({
+        signer: signer.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint,
+        userAccount,
+        bank,
+        bankTokenAccount,
+        userTokenAccount,
+        priceUpdate: feed,
+        fallbackPriceUpdate: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc({ commitment: 'confirmed' });
+
+  const updateBankConfig = (changes: Partial<BankConfig>) =>
+    program.methods
+      .updateBankConfig({ ...UNCHANGED_BANK_CONFIG, ...changes })
+      .accountsPartial({
+        owner: signer.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint,
+        bank,
+      })
+      .rpc({ commitment: 'confirmed' });
+
+  it('Init Market and Switchboard Bank', async () => {
+    await program.methods
+      .initLendingMarket(signer.publicKey, signer.publicKey)
+      .accounts({
+        owner: signer.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+      })
+      .signers([lendingMarket])
+      .rpc({ commitment: 'confirmed' });
+
+    await program.methods
+      .initBank({
+        liquidationThreshold: new BN(8000),
+        maxLtv: new BN(7500),
+        liquidationBonus: new BN(500),
+        liquidationCloseFactor: new BN(5000),
+        interestRateModel: {
+          baseRate: new BN(0),
+          optimalUtilization: new BN(8000),
+          slope1: new BN(500),
+          slope2: new BN(7500),
+        },
+        oracleSetup: { switchboardPull: {} },
+        oracle: feed,
+        priceFeedId: '',
+      })
+      .accountsPartial({
+        owner: signer.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint,
+        bank,
+        bankTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc({ commitment: 'confirmed' });
+
+    const bankData = await program.account.bank.fetch(bank);
+    assert.ok(bankData.oracle.equals(feed));
+  });
+
+  it('Deposit Priced by Switchboard', async () => {
+    await program.methods
+      .initUserTokenAccount('USDC')
+      .accountsPartial({
+        signer: signer.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint,
+        userTokenAccount,
+      })
+      .rpc({ commitment: 'confirmed' });
+
+    const userAta = await createAssociatedTokenAccount(
+      // @ts-ignore
+      banksClient,
+      signer,
+      mint,
+      signer.publicKey
+    );
+    // @ts-ignore
+    await mintTo(banksClient, signer, mint, userAta, signer, 1_000_000_000);
+
+    await deposit(100_000_000);
+
+    const bankData = await program.account.bank.fetch(bank);
+    assert.equal(bankData.totalDeposits.toString(), '100000000');
+  });
+
+  it('Reject Stale Switchboard Feed', async () => {
+    const clock = await banksClient.getClock();
+    context.setAccount(
+      feed,
+      buildSwitchboardPullFeed(1.0, 0.001, clock.unixTimestamp - BigInt(1_000))
+    );
+
+    await assert.rejects(deposit(1_000_000), /StalePrice/);
+  });
+
+  it('Accept Older Prices With a Longer Max Price Age', async () => {
+    await updateBankConfig({ maxPriceAge: new BN(2_000) });
+
+    await deposit(2_000_000);
+  });
+
+  it('Reject Wide Confidence Interval', async () => {
+    // 5% of the price, above the default 2%
+    const clock = await banksClient.getClock();
+    context.setAccount(feed, buildSwitchboardPullFeed(1.0, 0.05, clock.unixTimestamp));
+
+    await assert.rejects(deposit(3_000_000), /PriceConfidenceTooWide/);
+
+    await updateBankConfig({ maxConfidenceBps: new BN(1_000) });
+    await deposit(4_000_000);
+  });
+});
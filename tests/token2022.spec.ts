@@ -0,0 +1,321 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+// @ts-ignore
+import IDL from '../target/idl/lending2.json';
+import { Lending2 } from '../target/types/lending2';
+import { startAnchor } from 'solana-bankrun';
+import { BankrunProvider } from 'anchor-bankrun';
+import { BN, Program } from '@coral-xyz/anchor';
+import { Keypair, PublicKey, SystemProgram, Transaction, TransactionInstruction } from '@solana/web3.js';
+import {
+  ExtensionType,
+  TOKEN_2022_PROGRAM_ID,
+  createAssociatedTokenAccountIdempotentInstruction,
+  createInitializeMintInstruction,
+  createInitializePermanentDelegateInstruction,
+  createInitializeTransferHookInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  createMintToInstruction,
+  getAssociatedTokenAddressSync,
+  getMintLen,
+} from '@solana/spl-token';
+
+import { fixedPrice, marketFixture } from './marketFixture';
+
+const DECIMALS = 6;
+const TRANSFER_FEE_BPS = 100;
+
+// Stand-in hook program, only its id is checked when a bank is created
+const HOOK_PROGRAM_ID = Keypair.generate().publicKey;
+
+// Token-2022 `ConfidentialTransferInstruction::InitializeMint`, which
+// @solana/spl-token has no builder for
+const createInitializeConfidentialTransferMintInstruction = (
+  mint: PublicKey,
+  authority: PublicKey,
+  autoApproveNewAccounts: boolean
+) =>
+  new TransactionInstruction({
+    programId: TOKEN_2022_PROGRAM_ID,
+    keys: [{ pubkey: mint, isSigner: false, isWritable: true }],
+    data: Buffer.concat([
+      // TokenInstruction::ConfidentialTransferExtension, then InitializeMint
+      Buffer.from([27, 0]),
+      authority.toBuffer(),
+      Buffer.from([autoApproveNewAccounts ? 1 : 0]),
+      // No auditor
+      Buffer.alloc(32),
+    ]),
+  });
+
+// Token-2022 mints built locally, banks priced with a fixed oracle
+describe('Token-2022 Mint Tests', async () => {
+  const context = await startAnchor(
+    '',
+    [{ name: 'lending2', programId: new PublicKey(IDL.address) }],
+    []
+  );
+  const provider = new BankrunProvider(context);
+  const program = new Program<Lending2>(IDL as Lending2, provider);
+  const banksClient = context.banksClient;
+  const signer = provider.wallet.payer;
+
+  const lendingMarket = Keypair.generate();
+
+  const sendTransaction = async (transaction: Transaction, signers: Keypair[]) => {
+    transaction.recentBlockhash = context.lastBlockhash;
+    transaction.feePayer = signer.publicKey;
+    transaction.sign(signer, ...signers);
+    await banksClient.processTransaction(transaction);
+  };
+
+  const initializeExtension = (extension: ExtensionType, mint: PublicKey) => {
+    switch (extension) {
+      case ExtensionType.TransferFeeConfig:
+        return createInitializeTransferFeeConfigInstruction(
+          mint,
+          signer.publicKey,
+          signer.publicKey,
+          TRANSFER_FEE_BPS,
+          BigInt(1_000_000_000),
+          TOKEN_2022_PROGRAM_ID
+        );
+      case ExtensionType.TransferHook:
+        // No hook program yet, but the authority could set one later
+        return createInitializeTransferHookInstruction(
+          mint,
+          signer.publicKey,
+          PublicKey.default,
+          TOKEN_2022_PROGRAM_ID
+        );
+      default:
+        return createInitializePermanentDelegateInstruction(
+          mint,
+          signer.publicKey,
+          TOKEN_2022_PROGRAM_ID
+        );
+    }
+  };
+
+  const createMint2022 = async (
+    extension: ExtensionType,
+    initialize = (mint: PublicKey) => initializeExtension(extension, mint)
+  ) => {
+    const mint = Keypair.generate();
+    const space = getMintLen([extension]);
+    const rent = await banksClient.getRent();
+
+    const transaction = new Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: signer.publicKey,
+        newAccountPubkey: mint.publicKey,
+        space,
+        lamports: Number(rent.minimumBalance(BigInt(space))),
+        programId: TOKEN_2022_PROGRAM_ID,
+      }),
+      initialize(mint.publicKey),
+      createInitializeMintInstruction(
+        mint.publicKey,
+        DECIMALS,
+        signer.publicKey,
+        null,
+        TOKEN_2022_PROGRAM_ID
+      )
+    );
+    await sendTransaction(transaction, [mint]);
+
+    return mint.publicKey;
+  };
+
+  const fixture = marketFixture(program, banksClient, signer, lendingMarket);
+  const { bankAccounts, userAccountFor, userTokenAccountFor, initLendingMarket } = fixture;
+
+  const initBank = (mint: PublicKey) => fixture.initBank(mint, fixedPrice(1), TOKEN_2022_PROGRAM_ID);
+
+  it('Init Market', async () => {
+    await initLendingMarket();
+  });
+
+  it('Reject Permanent Delegate Mint', async () => {
+    const mint = await createMint2022(ExtensionType.PermanentDelegate);
+    await assert.rejects(initBank(mint), /UnsupportedMintExtension/);
+  });
+
+  it('Reject Updatable Transfer Hook Mint', async () => {
+    const mint = await createMint2022(ExtensionType.TransferHook);
+    await assert.rejects(initBank(mint), /UnsupportedMintExtension/);
+  });
+
+  it('Accept Only Allowlisted Transfer Hook Programs', async () => {
+    // A fixed hook, no authority can change the program afterwards
+    const createHookMint = () =>
+      createMint2022(ExtensionType.TransferHook, (mint) =>
+        createInitializeTransferHookInstruction(
+          mint,
+          PublicKey.default,
+          HOOK_PROGRAM_ID,
+          TOKEN_2022_PROGRAM_ID
+        )
+      );
+
+    await assert.rejects(initBank(await createHookMint()), /UnsupportedMintExtension/);
+
+    await program.methods
+      .updateLendingMarket({
+        owner: null,
+        guardian: null,
+        feeReceiver: null,
+        transferHookAllowlist: [HOOK_PROGRAM_ID],
+      })
+      .accounts({
+        owner: signer.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+      })
+      .rpc({ commitment: 'confirmed' });
+
+    const mint = await createHookMint();
+    await initBank(mint);
+
+    const bankData = await program.account.bank.fetch(bankAccounts(mint).bank);
+    assert.ok(bankData.mintAddress.equals(mint));
+  });
+
+  it('Reject Only Confidential-Only Mints', async () => {
+    const createConfidentialMint = (autoApproveNewAccounts: boolean) =>
+      createMint2022(ExtensionType.ConfidentialTransferMint, (mint) =>
+        createInitializeConfidentialTransferMintInstruction(
+          mint,
+          signer.publicKey,
+          autoApproveNewAccounts
+        )
+      );
+
+    // Confidential accounts need the authority's approval
+    await assert.rejects(initBank(await createConfidentialMint(false)), /UnsupportedMintExtension/);
+
+    // Confidential transfers are optional, public balances work as usual
+    await initBank(await createConfidentialMint(true));
+  });
+
+  const userAccount = userAccountFor(signer.publicKey);
+  let feeMint: PublicKey;
+
+  it('Deposit Credits Amount After Transfer Fee', async () => {
+    const mint = await createMint2022(ExtensionType.TransferFeeConfig);
+    feeMint = mint;
+    await initBank(mint);
+
+    const userTokenAccount = userTokenAccountFor(signer.publicKey, mint);
+    await program.methods
+      .initUserTokenAccount('FEE')
+      .accountsPartial({
+        signer: signer.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint,
+        userTokenAccount,
+      })
+      .rpc({ commitment: 'confirmed' });
+
+    const userAta = getAssociatedTokenAddressSync(
+      mint,
+      signer.publicKey,
+      false,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await sendTransaction(
+      new Transaction().add(
+        createAssociatedTokenAccountIdempotentInstruction(
+          signer.publicKey,
+          userAta,
+          signer.publicKey,
+          mint,
+          TOKEN_2022_PROGRAM_ID
+        ),
+        createMintToInstruction(mint, userAta, signer.publicKey, 10_000_000, [], TOKEN_2022_PROGRAM_ID)
+      ),
+      []
+    );
+
+    const { bank, bankTokenAccount } = bankAccounts(mint);
+    await program.methods
+      .deposit(new BN(1_000_000))
+      .accountsPartial({
+        signer: signer.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint,
+        userAccount,
+        bank,
+        bankTokenAccount,
+        userTokenAccount,
+        userTokenAssociatedAccount: userAta,
+        priceUpdate: bank,
+        fallbackPriceUpdate: null,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .rpc({ commitment: 'confirmed' });
+
+    // 1% of the transfer is withheld as a fee
+    const bankData = await program.account.bank.fetch(bank);
+    assert.equal(bankData.totalDeposits.toString(), '990000');
+
+    const userTokenData = await program.account.userTokenAccount.fetch(userTokenAccount);
+    assert.equal(userTokenData.depositAmount.toString(), '990000');
+  });
+
+  it('Repay All Covers the Transfer Fee', async () => {
+    const { bank, bankTokenAccount } = bankAccounts(feeMint);
+    const userTokenAccount = userTokenAccountFor(signer.publicKey, feeMint);
+    const userAta = getAssociatedTokenAddressSync(
+      feeMint,
+      signer.publicKey,
+      false,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    await program.methods
+      .borrow(new BN(500_000))
+      .accountsPartial({
+        signer: signer.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mintBorrow: feeMint,
+        userAccount,
+        bankBorrow: bank,
+        bankTokenAccountBorrow: bankTokenAccount,
+        userTokenAccountBorrow: userTokenAccount,
+        userAssociatedTokenAccount: userAta,
+        priceUpdate: bank,
+        fallbackPriceUpdate: null,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .remainingAccounts(
+        [bank, userTokenAccount, bank].map((pubkey) => ({
+          pubkey,
+          isSigner: false,
+          isWritable: false,
+        }))
+      )
+      .rpc({ commitment: 'confirmed' });
+
+    await program.methods
+      .repayAll()
+      .accountsPartial({
+        signer: signer.publicKey,
+        lendingMarket: lendingMarket.publicKey,
+        mint: feeMint,
+        bank,
+        bankTokenAccount,
+        userAccount,
+        userTokenAccount,
+        userAssociatedTokenAccount: userAta,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .rpc({ commitment: 'confirmed' });
+
+    // No dust debt is left behind by the fee
+    const userTokenData = await program.account.userTokenAccount.fetch(userTokenAccount);
+    assert.equal(userTokenData.borrowedShares.toString(), '0');
+
+    const bankData = await program.account.bank.fetch(bank);
+    assert.equal(bankData.totalBorrowedShares.toString(), '0');
+  });
+});